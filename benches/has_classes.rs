@@ -0,0 +1,65 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use regex::Regex;
+use rustywind::defaults::RE;
+use rustywind::utils::has_classes_with_regex;
+use rustywind::FinderRegex;
+
+/// A mix of small templates and large files without any class attribute, like the bundled and
+/// minified files that end up in a walked directory
+fn mixed_files() -> Vec<String> {
+    let template = r#"<div class="px-2 flex"><a class="mt-4 block">link</a></div>"#.repeat(20);
+    let script = "const value = compute(items.map((item) => item.id + 1));\n".repeat(20_000);
+    let styles = ".button { padding: 0.5rem; display: flex; }\n".repeat(20_000);
+
+    vec![template, script.clone(), styles, script]
+}
+
+fn bench_has_classes(c: &mut Criterion) {
+    let files = mixed_files();
+    let mut group = c.benchmark_group("has_classes");
+
+    group.bench_function("regex only", |b| {
+        b.iter(|| {
+            files
+                .iter()
+                .filter(|file| RE.is_match(black_box(file)))
+                .count()
+        })
+    });
+
+    group.bench_function("with prescreen", |b| {
+        b.iter(|| {
+            files
+                .iter()
+                .filter(|file| has_classes_with_regex(black_box(file), &FinderRegex::DefaultRegex))
+                .count()
+        })
+    });
+
+    // a custom finder starting with a literal is already prefiltered by the regex crate, looking
+    // for the literal first only adds a second pass over the files that contain it
+    let custom = Regex::new(r"tw\(.*?\)").unwrap();
+
+    group.bench_function("custom regex only", |b| {
+        b.iter(|| {
+            files
+                .iter()
+                .filter(|file| custom.is_match(black_box(file)))
+                .count()
+        })
+    });
+
+    group.bench_function("custom regex with prescreen", |b| {
+        b.iter(|| {
+            files
+                .iter()
+                .filter(|file| file.contains("tw(") && custom.is_match(black_box(file)))
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_has_classes);
+criterion_main!(benches);
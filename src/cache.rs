@@ -0,0 +1,72 @@
+//! The on-disk cache of the files that are already sorted, so they can be skipped on the next run
+
+use eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Where the cache is saved, relative to the current directory
+pub const CACHE_FILE_NAME: &str = ".rustywind-cache";
+
+/// Maps the files that were already sorted to the hash of their contents. The `key` is a hash of
+/// everything that changes how files are sorted (the config file, the sort options and the version),
+/// when it changes the whole cache is invalidated. The files are behind a `Mutex` so they can be
+/// recorded while the files are sorted in parallel
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {
+    key: u64,
+    files: Mutex<HashMap<PathBuf, u64>>,
+}
+
+impl Cache {
+    pub fn new(key: u64) -> Self {
+        Cache {
+            key,
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Loads the cache saved at `path`, a missing, unreadable or outdated cache starts empty
+    pub fn load(path: &Path, key: u64) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Cache>(&contents).ok())
+            .filter(|cache| cache.key == key)
+            .unwrap_or_else(|| Cache::new(key))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string(self)?;
+
+        fs::write(path, contents)
+            .wrap_err_with(|| format!("Unable to save the cache to {}", path.display()))
+    }
+
+    /// Whether the file was already sorted the last time it had these contents
+    pub fn is_sorted(&self, path: &Path, contents: &str) -> bool {
+        self.files.lock().unwrap().get(path) == Some(&hash(contents))
+    }
+
+    /// Records that the file is sorted with these contents
+    pub fn insert(&mut self, path: PathBuf, contents: &str) {
+        self.files.get_mut().unwrap().insert(path, hash(contents));
+    }
+
+    /// Same as `insert`, for the files recorded while sorting, see `Options::apply`
+    pub fn record(&self, path: &Path, contents: &str) {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_owned(), hash(contents));
+    }
+}
+
+pub fn hash(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,689 @@
+use clap::{ArgEnum, Parser};
+use indoc::indoc;
+use tracing::level_filters::LevelFilter;
+
+use crate::options::{
+    ClassSeparator, CustomClassOrder, CustomClassPosition, IgnoredClassPosition, TailwindVersion,
+};
+
+#[derive(Parser, Debug)]
+#[clap(name = "RustyWind", author, version, about, long_about = None)]
+#[clap(args_override_self = true, arg_required_else_help = true)]
+#[clap(override_usage = indoc!("
+Run rustywind with a path to get a list of files that will be changed
+      rustywind . --dry-run
+
+    If you want to reorganize all classes in place, and change the files run with the `--write` flag
+      rustywind --write .
+
+    To print only the file names that would be changed run with the `--check-formatted` flag
+      rustywind --check-formatted .
+
+    If you want to run it on your STDIN, you can do:
+      echo \"<FILE CONTENTS>\" | rustywind --stdin
+                 
+    rustywind [FLAGS] <PATH>"))]
+pub struct Cli {
+    #[clap(
+        name = "file-or-dir",
+        help = "A file or directory to run on",
+        value_name = "PATH",
+        required_unless_present_any = &["stdin", "files-from", "files", "staged", "since", "print-sort-order", "print-config", "init"]
+    )]
+    pub file_or_dir: Vec<String>,
+
+    #[clap(
+        long,
+        help = "Uses stdin instead of a file or folder",
+        conflicts_with_all = &["dry-run", "diff"],
+        required_unless_present_any = &["file-or-dir", "files-from", "files", "staged", "since", "print-sort-order", "print-config", "init"],
+    )]
+    pub stdin: bool,
+
+    #[clap(
+        long,
+        value_name = "FILE",
+        help = "Runs on the newline separated list of files in FILE instead of a path, \
+        use - to read the list from stdin, ex: git diff --name-only | rustywind --files-from -",
+        conflicts_with_all = &["stdin", "file-or-dir"]
+    )]
+    pub files_from: Option<String>,
+
+    #[clap(
+        long,
+        value_name = "GLOB",
+        help = "Runs on the files matching GLOB instead of a path, the glob is expanded by \
+        rustywind so it works the same on every shell, ex: --files \"src/**/*.html\". \
+        Can be passed multiple times",
+        conflicts_with_all = &["stdin", "file-or-dir", "files-from"]
+    )]
+    pub files: Vec<String>,
+
+    #[clap(
+        long,
+        help = "Runs on the files staged in git instead of a path, the staged files that also \
+        have unstaged changes are skipped",
+        conflicts_with_all = &["stdin", "file-or-dir", "files-from", "files"]
+    )]
+    pub staged: bool,
+
+    #[clap(
+        long,
+        value_name = "REF",
+        help = "Runs on the files changed on the current branch since REF instead of a path, the \
+        same files as git diff --name-only REF...HEAD, ex: --since main",
+        conflicts_with_all = &["stdin", "file-or-dir", "files-from", "files", "staged"]
+    )]
+    pub since: Option<String>,
+
+    #[clap(
+        long,
+        help = "When used with --staged and --write, stages the files again after sorting them",
+        requires_all = &["staged", "write"]
+    )]
+    pub restage: bool,
+
+    #[clap(
+        long,
+        help = "Keeps running after sorting the files, and sorts the files again when they change",
+        conflicts_with_all = &["stdin", "files-from", "staged", "since", "check-formatted"]
+    )]
+    pub watch: bool,
+
+    #[clap(
+        long,
+        help = "Also runs on the files ignored by .gitignore and .ignore files, the \
+        .rustywindignore files are still respected"
+    )]
+    pub no_ignore: bool,
+
+    #[clap(
+        long,
+        help = "Also runs on the hidden files and directories, ex: .storybook, the .git directory \
+        is always skipped"
+    )]
+    pub hidden: bool,
+
+    #[clap(
+        long,
+        help = "Walks into the symlinked directories, a file is only sorted once when it is found \
+        through multiple paths and the symlinks are resolved before writing"
+    )]
+    pub follow_symlinks: bool,
+
+    #[clap(
+        long,
+        value_name = "BYTES",
+        help = "Skips the files larger than BYTES, ex: huge generated bundles, they are not read"
+    )]
+    pub max_filesize: Option<u64>,
+
+    #[clap(
+        long,
+        number_of_values = 2,
+        value_names = &["START", "END"],
+        help = "With --stdin, only sorts the classes found between the START and END byte \
+        offsets, the rest of the input is printed as is",
+        conflicts_with = "file-or-dir"
+    )]
+    pub range: Option<Vec<usize>>,
+
+    #[clap(
+        long,
+        value_name = "FILE",
+        help = "Writes the sorted output to FILE instead, with --stdin or a single file as the \
+        input. FILE is created or truncated, it can be the input file itself",
+        conflicts_with_all = &["write", "dry-run", "check-formatted", "diff", "watch", "files-from", "staged", "since"]
+    )]
+    pub output: Option<String>,
+
+    #[clap(
+        long,
+        help = "Changes the files in place with the reorganized classes",
+        conflicts_with_all = &["dry-run", "check-formatted", "diff"],
+    )]
+    pub write: bool,
+
+    #[clap(
+        long,
+        help = "With --write, shows the diff of each changed file and asks whether to write it, \
+        answer y (yes), n (no), a (all, writes the rest without asking) or q (quit)",
+        requires = "write",
+        conflicts_with_all = &["stdin", "files-from", "watch", "quiet", "output-format"]
+    )]
+    pub interactive: bool,
+
+    #[clap(
+        long,
+        help = "Prints the files that would be changed, with every class string that would be \
+        reordered as line:column before -> after. With --output-format json, prints them as a json plan of \
+        the path and the before and after of the changes of each file",
+        conflicts_with_all = &["stdin", "write", "check-formatted", "diff"]
+    )]
+    pub dry_run: bool,
+
+    #[clap(
+        long,
+        help = "In dry run mode, exits with status 1 when any file would be changed, \
+        the output is the same",
+        conflicts_with_all = &["stdin", "write", "check-formatted", "diff", "watch"]
+    )]
+    pub exit_code_on_change: bool,
+
+    #[clap(
+        long,
+        help = "Warns about the class and className attributes that could not be sorted, \
+        ex: a class=\"... without its closing quote, and exits with status 1 if there are any",
+        conflicts_with_all = &["stdin", "watch"]
+    )]
+    pub strict: bool,
+
+    #[clap(
+        long,
+        help = "Checks if the files are already formatted, exits with 1 if not formatted",
+        conflicts_with_all = &["write", "dry-run", "diff"]
+    )]
+    pub check_formatted: bool,
+
+    #[clap(
+        long,
+        help = "Prints a unified diff of the changes that would be made to the files",
+        conflicts_with_all = &["stdin", "write", "dry-run", "check-formatted"]
+    )]
+    pub diff: bool,
+
+    #[clap(
+        long,
+        help = "With --diff, prints the old and new class list of each changed class string \
+        instead of a unified diff, with --color the moved classes are highlighted",
+        requires = "diff"
+    )]
+    pub only_changed: bool,
+
+    #[clap(long, help = "Colors the output of --diff", requires = "diff")]
+    pub color: bool,
+
+    #[clap(
+        long,
+        help = "When used with --write, saves the original contents to <FILE>.bak before overwriting",
+        requires = "write"
+    )]
+    pub backup: bool,
+
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "FORMAT",
+        default_value = "human",
+        help = "When set to json, prints a json report of the files with classes and the class \
+        strings that were reordered, instead of the files. With --dry-run, only the files \
+        that would be changed are printed. With --check-formatted, prints whether the files \
+        are formatted and the files that are not, as { \"formatted\": bool, \"files\": [] }",
+        conflicts_with_all = &["write", "diff"]
+    )]
+    pub output_format: OutputFormat,
+
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "VERSION",
+        default_value = "3",
+        help = "The Tailwind version used for the default sort order"
+    )]
+    pub tailwind_version: TailwindVersion,
+
+    #[clap(
+        long,
+        value_name = "PREFIX",
+        help = "The prefix of the Tailwind config, ex: tw-, the prefixed classes are sorted like \
+        the classes without it, ex: md:tw-px-2, !tw-px-2 and -tw-mt-2. The classes without the \
+        prefix are custom classes"
+    )]
+    pub prefix: Option<String>,
+
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "ORDER",
+        help = "How to order the classes that are not tailwind classes, defaults to the order \
+        they were written in"
+    )]
+    pub sort_custom: Option<CustomClassOrder>,
+
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "POSITION",
+        help = "Whether the classes that are not tailwind classes are placed before or after \
+        the tailwind classes, defaults to last"
+    )]
+    pub custom_class_position: Option<CustomClassPosition>,
+
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "SEPARATOR",
+        help = "What separates the classes, with comma the classes can also be separated by \
+        commas and quoted, ex: \"px-2\", \"flex\", the original separators are kept. Defaults \
+        to whitespace"
+    )]
+    pub class_separator: Option<ClassSeparator>,
+
+    #[clap(
+        long,
+        value_name = "SEPARATOR",
+        help = "Joins the sorted classes with SEPARATOR instead of a single space. It has to be \
+        whitespace, ex: a line break, or a comma with --class-separator comma, ex: \", \", which \
+        only joins the lists that are already separated by commas",
+        conflicts_with_all = &["preserve-whitespace", "max-line-length"]
+    )]
+    pub join_separator: Option<String>,
+
+    #[clap(
+        long,
+        help = "Keeps the classes that are not tailwind classes where they were written, only the \
+        tailwind classes are reordered around them",
+        conflicts_with_all = &["custom-class-position", "sort-custom"]
+    )]
+    pub preserve_custom_position: bool,
+
+    #[clap(
+        long,
+        value_name = "CLASS",
+        help = "Never moves CLASS between the other classes, ex: a js-toggle hook, it's placed at \
+        the --ignored-class-position. Can be passed multiple times, the classes keep the order \
+        they were written in"
+    )]
+    pub ignore_class: Vec<String>,
+
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "POSITION",
+        help = "Where the classes of --ignore-class are placed, first, last or original to keep \
+        them where they were written, defaults to first"
+    )]
+    pub ignored_class_position: Option<IgnoredClassPosition>,
+
+    #[clap(
+        long,
+        help = "Only prints the errors, the sorted output of --stdin and the output of --diff and \
+        --output-format json, this wins over --verbose"
+    )]
+    pub quiet: bool,
+
+    #[clap(
+        long,
+        help = "Prints the classes in the order they are sorted, one per line, and exits. \
+        Includes the sortOrder of the config file, if there is one",
+        conflicts_with_all = &["stdin", "files-from", "staged", "since", "watch"]
+    )]
+    pub print_sort_order: bool,
+
+    #[clap(
+        long,
+        help = "Prints the options in effect after reading the flags and the config files, like \
+        the config files, finders and sorter used, and exits. Printed as json with \
+        --output-format json",
+        conflicts_with_all = &["stdin", "watch", "print-sort-order", "count"]
+    )]
+    pub print_config: bool,
+
+    #[clap(
+        long,
+        help = "Writes a starter rustywind.json into the current directory and exits",
+        conflicts_with_all = &["file-or-dir", "stdin", "files-from", "staged", "since", "print-sort-order"]
+    )]
+    pub init: bool,
+
+    #[clap(
+        long,
+        help = "With --init, overwrites the existing rustywind.json",
+        requires = "init"
+    )]
+    pub force: bool,
+
+    #[clap(
+        long,
+        help = "Prints how many of the files have classes that the finder matches, without \
+        sorting them, and exits",
+        conflicts_with_all = &["stdin", "write", "dry-run", "check-formatted", "diff", "watch", "output"]
+    )]
+    pub count: bool,
+
+    #[clap(
+        long,
+        help = "With --count, also prints the files that have classes",
+        requires = "count"
+    )]
+    pub list_files: bool,
+
+    #[clap(
+        long,
+        help = "Prints the classes that are not in the sort order, with or without their \
+        variants, with how many times each one is used, most used first, without sorting them, \
+        and exits. Finds the typos, ex: flexx, and the classes of another Tailwind version",
+        conflicts_with_all = &["stdin", "write", "dry-run", "check-formatted", "diff", "watch", "output", "count"]
+    )]
+    pub dump_unknown_classes: bool,
+
+    #[clap(
+        long,
+        help = "Prints debug information to stderr, like the config file and finders being used"
+    )]
+    pub verbose: bool,
+
+    #[clap(
+        long,
+        arg_enum,
+        value_name = "LEVEL",
+        help = "The level of the logs printed to stderr, defaults to warn, or to the RUST_LOG \
+        environment variable. This wins over --quiet and --verbose"
+    )]
+    pub log_level: Option<LogLevel>,
+
+    #[clap(
+        long,
+        help = "Prints a summary of the files scanned and changed, and the class strings sorted",
+        conflicts_with = "stdin"
+    )]
+    pub stats: bool,
+
+    #[clap(
+        long,
+        help = "Disables the .rustywind-cache file, that is used to skip the files that \
+        haven't changed since they were last sorted, it is only written with --write"
+    )]
+    pub no_cache: bool,
+
+    #[clap(
+        long,
+        value_name = "N",
+        help = "The number of files to process in parallel, defaults to the number of CPUs"
+    )]
+    pub jobs: Option<usize>,
+
+    #[clap(
+        long,
+        value_name = "N",
+        help = "The number of threads used to walk the directories, 0 uses the available \
+        parallelism, defaults to a single thread"
+    )]
+    pub threads: Option<usize>,
+
+    #[clap(long, help = "When set, RustyWind will not delete duplicated classes")]
+    pub allow_duplicates: bool,
+
+    #[clap(
+        long,
+        help = "Only reorders the classes, the duplicated classes are never removed, so the \
+        sorted classes are always the same classes as before",
+        conflicts_with_all = &["warn-duplicates", "fail-on-duplicate"]
+    )]
+    pub no_dedup: bool,
+
+    #[clap(
+        long,
+        help = "Lowercases the classes, so ex: Flex is sorted and deduplicated as flex. The \
+        arbitrary values in brackets and parentheses keep their case, ex: bg-[URL('A.png')]"
+    )]
+    pub lowercase_classes: bool,
+
+    #[clap(
+        long,
+        help = "Prints a warning on stderr for every class string that had duplicated classes \
+        removed, so they can be cleaned up in the source",
+        conflicts_with = "allow-duplicates"
+    )]
+    pub warn_duplicates: bool,
+
+    #[clap(
+        long,
+        help = "Reports every class string that has duplicated classes as an error and exits \
+        with status 1, the duplicates are still removed",
+        conflicts_with = "allow-duplicates"
+    )]
+    pub fail_on_duplicate: bool,
+
+    #[clap(
+        long,
+        visible_alias = "no-normalize-whitespace",
+        help = "Keeps the original whitespace and line breaks between the classes, \
+        instead of trimming them and separating the sorted classes with single spaces"
+    )]
+    pub preserve_whitespace: bool,
+
+    #[clap(
+        long,
+        value_name = "N",
+        help = "Wraps the sorted classes onto multiple lines when they go past N columns, the \
+        lines are indented to the start of the attribute",
+        conflicts_with = "preserve-whitespace"
+    )]
+    pub max_line_length: Option<usize>,
+
+    #[clap(
+        long,
+        value_name = "N",
+        help = "The width of a tab when measuring the lines of --max-line-length, and of the tabs \
+        of --use-tabs, defaults to 4",
+        requires = "max-line-length"
+    )]
+    pub tab_width: Option<usize>,
+
+    #[clap(
+        long,
+        help = "Indents the lines wrapped by --max-line-length with tabs, and spaces for the rest \
+        of the alignment, instead of reusing the indentation of the line of the attribute",
+        requires = "max-line-length"
+    )]
+    pub use_tabs: bool,
+
+    #[clap(
+        long,
+        help = "When set, RustyWind will use the config file to derive configurations. \
+        The config file can be json, or toml when it has a .toml extension, \
+        e.g. { \"sortOrder\": [\"class1\", ...] }. Can be passed multiple times, \
+        the later config files are merged into the earlier ones. Use - to read the json config \
+        from stdin, ex: generate-config | rustywind --config-file - --write ."
+    )]
+    pub config_file: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "FILE",
+        help = "Reads the sort order from FILE, one class per line, instead of the sortOrder of \
+        the config file. The other config options still apply, like extendDefault"
+    )]
+    pub sort_order_file: Option<String>,
+
+    #[clap(
+        long,
+        help = "When no config file is passed, RustyWind looks for a rustywind.json or \
+        rustywind.toml in the directory of the path and its parents, this disables that",
+        conflicts_with = "config-file"
+    )]
+    pub no_auto_config: bool,
+
+    #[clap(
+        long,
+        help = "When set, RustyWind will ignore this list of files, a glob like \
+        src/**/*.generated.html ignores every file it matches"
+    )]
+    pub ignored_files: Option<Vec<String>>,
+
+    #[clap(
+        long,
+        value_name = "DIR",
+        help = "Refuses to write any file that is not inside of DIR, after resolving the symlinks. \
+        Can be passed multiple times to allow multiple directories"
+    )]
+    pub fix_only: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "EXTENSIONS",
+        use_value_delimiter = true,
+        help = "Only runs on the files with these comma separated extensions, ex: html,jsx,vue"
+    )]
+    pub include_ext: Option<Vec<String>>,
+
+    #[clap(
+        long,
+        value_name = "EXTENSIONS",
+        use_value_delimiter = true,
+        help = "Never runs on the files with these comma separated extensions, ex: min.js"
+    )]
+    pub exclude_ext: Option<Vec<String>>,
+
+    #[clap(long, help = "Uses a custom regex instead of default one")]
+    pub custom_regex: Option<String>,
+
+    #[clap(
+        long,
+        help = "Matches the finders in any case, ex: CLASS=\"...\", the classes are still sorted \
+        case-sensitively. The finders of .vue, .tsx, .jsx, .css and .scss files are not \
+        picked automatically, pass their preset instead"
+    )]
+    pub ignore_case: bool,
+
+    #[clap(
+        long,
+        value_name = "FIELD",
+        help = "Sorts the classes of the strings at this field of the .json files instead of \
+        using the finders, ex: classes or button.classes, * matches any key. The rest of the \
+        file keeps its formatting and the files that are not valid json are left as is",
+        conflicts_with = "stdin"
+    )]
+    pub json_field: Option<String>,
+
+    #[clap(
+        long,
+        help = "Also sorts the string arguments of clsx(), classnames() and cn() calls. \
+        The function names can be changed with `classFunctions` in the config file",
+        conflicts_with = "custom-regex"
+    )]
+    pub clsx: bool,
+
+    #[clap(
+        long,
+        help = "Also sorts the string arguments of tailwind-merge twMerge() and tw() calls",
+        conflicts_with = "custom-regex"
+    )]
+    pub tw_merge: bool,
+
+    #[clap(
+        long,
+        help = "Also sorts the strings in Vue :class and v-bind:class bindings, \
+        this is always on for .vue files",
+        conflicts_with = "custom-regex"
+    )]
+    pub vue: bool,
+
+    #[clap(
+        long,
+        help = "Also sorts the strings in Angular [ngClass] bindings, [class.foo] bindings are \
+        left as is",
+        conflicts_with = "custom-regex"
+    )]
+    pub angular: bool,
+
+    #[clap(
+        long,
+        help = "Also sorts the Alpine.js :class and x-bind:class bindings, the string and the \
+        keys of the object, ex: :class=\"{ 'px-2 flex': open }\", the conditions are left as is",
+        conflicts_with = "custom-regex"
+    )]
+    pub alpine: bool,
+
+    #[clap(
+        long,
+        help = "Also sorts the string of Leptos class=(\"...\", condition) attributes",
+        conflicts_with = "custom-regex"
+    )]
+    pub leptos: bool,
+
+    #[clap(
+        long,
+        help = "Also sorts the strings inside of HEEx class={...} bindings, always on for .heex and \
+        .eex files",
+        conflicts_with = "custom-regex"
+    )]
+    pub heex: bool,
+
+    #[clap(
+        long,
+        help = "Also sorts the dotted class chains of Maud elements, ex: div.px-2.flex { }",
+        conflicts_with = "custom-regex"
+    )]
+    pub maud: bool,
+
+    #[clap(
+        long,
+        help = "Also sorts the classes of @apply directives, this is always on for .css and \
+        .scss files",
+        conflicts_with = "custom-regex"
+    )]
+    pub css: bool,
+
+    #[clap(
+        long,
+        help = "Also sorts the classes of className={`...`} template literals, leaving the ${...} \
+        interpolations untouched, and each string of className={\"...\" + base} concatenations, \
+        this is always on for .tsx and .jsx files",
+        conflicts_with = "custom-regex"
+    )]
+    pub tsx: bool,
+
+    #[clap(
+        long,
+        help = "Also sorts the base classes and the variant values of class-variance-authority \
+        cva() calls",
+        conflicts_with = "custom-regex"
+    )]
+    pub cva: bool,
+
+    #[clap(
+        long,
+        visible_alias = "jinja",
+        help = "Also sorts the class attributes with Handlebars or Jinja {{ ... }} interpolations \
+        and {% ... %} tags, only the classes between them are sorted",
+        conflicts_with = "custom-regex"
+    )]
+    pub handlebars: bool,
+
+    #[clap(
+        long,
+        help = "Sorts the class attributes of Markdown and MDX files, except for the ones inside \
+        of fenced code blocks",
+        conflicts_with = "custom-regex"
+    )]
+    pub mdx: bool,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(log_level: LogLevel) -> Self {
+        match log_level {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
@@ -31,6 +31,10 @@ pub static VARIANTS: Lazy<Vec<&'static str>> = Lazy::new(|| {
     ]
 });
 
+/// The responsive variants, from the smallest to the largest screen. They are always sorted
+/// together and in this order, mobile-first
+pub const BREAKPOINTS: [&str; 9] = ["sm", "md", "lg", "xl", "2xl", "3xl", "4xl", "5xl", "6xl"];
+
 pub static VARIANT_SEARCHER: Lazy<AhoCorasick> = Lazy::new(|| {
     AhoCorasickBuilder::new()
         .anchored(true)
@@ -1,11 +1,57 @@
+use itertools::Itertools;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::collections::HashMap;
 
+/// Matches a `class` or `className` attribute, the classes can have fractions, ex: `-translate-x-1/2`,
+/// and a bracketed arbitrary value can contain any character other than the closing quote,
+/// ex: `bg-[url('a b.png')]`. The value can be double, single or backtick quoted, or an unquoted
+/// HTML5 value followed by whitespace or `>`, ex: `class=flex>`. An unquoted value is only matched
+/// in the `class` attribute of a tag, without whitespace around the `=`, so the JS assignments,
+/// ex: `el.className = isOpen ? a : b`, are not class attributes
 pub static RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"\b(?:class(?:Name)*\s*=\s*["'])([_a-zA-Z0-9\.\s\-:\[\]]+)["']"#).unwrap()
+    Regex::new(concat!(
+        r#"\b(?:class(?:Name)*\s*=\s*)(?:"((?:[_a-zA-Z0-9\.\s\-:/!]|\[[^\]"]*\])+)"|'((?:[_a-zA-Z0-9\.\s\-:/!]|\[[^\]']*\])+)'"#,
+        r#"|`((?:[_a-zA-Z0-9\.\s\-:/!]|\[[^\]`]*\])+)`)"#,
+        r#"|<[a-zA-Z][\w:-]*\s(?:[^<>]*\s)?class=((?:[_a-zA-Z0-9\.\-:/!]|\[[^\]\s"'`]*\])+)(?:\s|>)"#,
+    ))
+    .unwrap()
 });
 
+/// The same as `RE` but `class` and `className` are matched in any case, ex: `CLASS="px-2 flex"`,
+/// used with `--ignore-case`
+pub static RE_IGNORE_CASE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(&format!("(?i){}", RE.as_str())).unwrap());
+
+/// Matches the start of a quoted `class` or `className` attribute, used by `--strict` to find the
+/// attributes that the finder couldn't match. Bound attributes like `:class` and `x-bind:class` are
+/// not matched, the start of the attribute is in the first capture group
+pub static CLASS_ATTRIBUTE_START_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?:^|[^:\w-])(class(?:Name)?\s*=\s*["'])"#).unwrap());
+
+/// Matches one class of a dotted class chain, ex: `.flex`, `."hover:flex"` or `.flex[active]`, the
+/// class is in the first capture group, or in the second one when it's quoted
+pub static DOTTED_CLASS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\.(?:([\w-]+)|"([^"]*)")(?:\[[^\]]*\])?"#).unwrap());
+
+/// Matches a single or double quoted string literal, used inside of the class function calls
+pub static STRING_LITERAL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#""((?:[_a-zA-Z0-9\.\s\-:/!]|\[[^\]"']*\])+)"|'((?:[_a-zA-Z0-9\.\s\-:/!]|\[[^\]"']*\])+)'"#,
+    )
+    .unwrap()
+});
+
+/// The default functions sorted with `--clsx`, override them with `classFunctions` in the config file
+pub const CLASS_FUNCTIONS: [&str; 3] = ["clsx", "classnames", "cn"];
+
+/// The structural classes placed before the other classes with `pinMarkerClasses`, the named
+/// groups and peers, ex: `group/item`, are marker classes too
+pub const MARKER_CLASSES: [&str; 3] = ["container", "group", "peer"];
+
+/// The tailwind-merge functions sorted with `--tw-merge`
+pub const TW_MERGE_FUNCTIONS: [&str; 2] = ["twMerge", "tw"];
+
 pub static SORTER: Lazy<HashMap<String, usize>> = Lazy::new(|| {
     vec![
         "container",
@@ -4966,3 +5012,508 @@ pub static SORTER: Lazy<HashMap<String, usize>> = Lazy::new(|| {
     .map(|(index, class)| (class.to_string(), index))
     .collect()
 });
+
+/// The default sort order for Tailwind v4, selected with `--tailwind-version 4`.
+///
+/// It is built from the Tailwind v3 `SORTER` plus the utilities added in v4, regrouped to follow
+/// the CSS property order Tailwind v4 uses to sort its utilities (see `TAILWIND_V4_PROPERTY_ORDER`).
+/// Within a property group the v3 order is kept and new v4 utilities come last.
+pub static SORTER_V4: Lazy<HashMap<String, usize>> = Lazy::new(|| {
+    SORTER
+        .iter()
+        .sorted_by_key(|(_class, index)| **index)
+        .map(|(class, _index)| class.to_string())
+        .chain(tailwind_v4_additions())
+        .unique()
+        .sorted_by_cached_key(|class| tailwind_v4_property_position(class))
+        .enumerate()
+        .map(|(index, class)| (class, index))
+        .collect()
+});
+
+/// Groups of utilities in the order of the CSS property they set in Tailwind v4. A pattern ending
+/// with `-` matches every class starting with it, otherwise the class has to match exactly, the
+/// exact match or the longest prefix wins.
+const TAILWIND_V4_PROPERTY_ORDER: &[&[&str]] = &[
+    &["pointer-events-"],
+    &["visible", "invisible", "collapse"],
+    &[
+        "static",
+        "fixed",
+        "absolute",
+        "relative",
+        "sticky",
+        "sr-only",
+        "not-sr-only",
+    ],
+    &["inset-"],
+    &["inset-x-"],
+    &["inset-y-"],
+    &["start-"],
+    &["end-"],
+    &["top-"],
+    &["right-"],
+    &["bottom-"],
+    &["left-"],
+    &["isolate", "isolation-auto"],
+    &["z-"],
+    &["order-"],
+    &["col-"],
+    &["row-"],
+    &["float-"],
+    &["clear-"],
+    &["container"],
+    &["m-"],
+    &["mx-"],
+    &["my-"],
+    &["ms-"],
+    &["me-"],
+    &["mt-"],
+    &["mr-"],
+    &["mb-"],
+    &["ml-"],
+    &["box-border", "box-content", "border-box"],
+    &["line-clamp-"],
+    &[
+        "block",
+        "inline-block",
+        "inline",
+        "flex",
+        "inline-flex",
+        "table",
+        "inline-table",
+        "table-caption",
+        "table-cell",
+        "table-column",
+        "table-column-group",
+        "table-footer-group",
+        "table-header-group",
+        "table-row-group",
+        "table-row",
+        "flow-root",
+        "grid",
+        "inline-grid",
+        "contents",
+        "list-item",
+        "hidden",
+    ],
+    &["field-sizing-"],
+    &["aspect-"],
+    &["size-"],
+    &["h-"],
+    &["max-h-"],
+    &["min-h-"],
+    &["w-"],
+    &["max-w-"],
+    &["min-w-"],
+    &["flex-1", "flex-auto", "flex-initial", "flex-none"],
+    &["shrink", "shrink-", "flex-shrink", "flex-shrink-"],
+    &["grow", "grow-", "flex-grow", "flex-grow-"],
+    &["basis-"],
+    &["table-auto", "table-fixed"],
+    &["caption-"],
+    &["border-collapse", "border-separate"],
+    &["border-spacing-"],
+    &["origin-"],
+    &["translate-"],
+    &["scale-"],
+    &["rotate-"],
+    &["skew-"],
+    &[
+        "transform",
+        "transform-gpu",
+        "transform-cpu",
+        "transform-none",
+    ],
+    &["animate-"],
+    &["cursor-"],
+    &["touch-"],
+    &["select-"],
+    &["resize", "resize-"],
+    &["snap-"],
+    &[
+        "scroll-m-",
+        "scroll-mx-",
+        "scroll-my-",
+        "scroll-ms-",
+        "scroll-me-",
+        "scroll-mt-",
+        "scroll-mr-",
+        "scroll-mb-",
+        "scroll-ml-",
+    ],
+    &[
+        "scroll-p-",
+        "scroll-px-",
+        "scroll-py-",
+        "scroll-ps-",
+        "scroll-pe-",
+        "scroll-pt-",
+        "scroll-pr-",
+        "scroll-pb-",
+        "scroll-pl-",
+    ],
+    &["list-inside", "list-outside"],
+    &["list-none", "list-disc", "list-decimal"],
+    &["list-image-"],
+    &["appearance-"],
+    &["columns-"],
+    &["break-before-"],
+    &["break-inside-"],
+    &["break-after-"],
+    &["auto-cols-"],
+    &["grid-cols-"],
+    &["grid-flow-"],
+    &["auto-rows-"],
+    &["grid-rows-"],
+    &[
+        "flex-row",
+        "flex-row-reverse",
+        "flex-col",
+        "flex-col-reverse",
+    ],
+    &["flex-wrap", "flex-wrap-reverse", "flex-nowrap"],
+    &["place-content-"],
+    &["place-items-"],
+    &["content-"],
+    &["items-"],
+    &["justify-"],
+    &["justify-items-"],
+    &["gap-"],
+    &["gap-x-"],
+    &["gap-y-"],
+    &["space-x-"],
+    &["space-y-"],
+    &["divide-x", "divide-x-", "divide-y", "divide-y-"],
+    &[
+        "divide-solid",
+        "divide-dashed",
+        "divide-dotted",
+        "divide-double",
+        "divide-none",
+    ],
+    &["divide-"],
+    &["place-self-"],
+    &["self-"],
+    &["justify-self-"],
+    &["truncate", "overflow-"],
+    &["overflow-x-"],
+    &["overflow-y-"],
+    &["overscroll-"],
+    &["scroll-auto", "scroll-smooth"],
+    &["rounded", "rounded-"],
+    &[
+        "border",
+        "border-0",
+        "border-2",
+        "border-4",
+        "border-8",
+        "border-x",
+        "border-x-",
+        "border-y",
+        "border-y-",
+        "border-s",
+        "border-s-",
+        "border-e",
+        "border-e-",
+        "border-t",
+        "border-t-",
+        "border-r",
+        "border-r-",
+        "border-b",
+        "border-b-",
+        "border-l",
+        "border-l-",
+    ],
+    &[
+        "border-solid",
+        "border-dashed",
+        "border-dotted",
+        "border-double",
+        "border-hidden",
+        "border-none",
+    ],
+    &["border-"],
+    &["bg-"],
+    &[
+        "bg-none",
+        "bg-gradient-",
+        "bg-linear-",
+        "bg-radial",
+        "bg-radial-",
+        "bg-conic",
+        "bg-conic-",
+    ],
+    &["from-"],
+    &["via-"],
+    &["to-"],
+    &["box-decoration-", "decoration-clone", "decoration-slice"],
+    &["bg-auto", "bg-cover", "bg-contain"],
+    &["bg-fixed", "bg-local", "bg-scroll"],
+    &["bg-clip-"],
+    &[
+        "bg-bottom",
+        "bg-center",
+        "bg-left",
+        "bg-left-bottom",
+        "bg-left-top",
+        "bg-right",
+        "bg-right-bottom",
+        "bg-right-top",
+        "bg-top",
+    ],
+    &["bg-repeat", "bg-no-repeat", "bg-repeat-"],
+    &["bg-origin-"],
+    &["fill-"],
+    &["stroke-"],
+    &["stroke-0", "stroke-1", "stroke-2"],
+    &[
+        "object-contain",
+        "object-cover",
+        "object-fill",
+        "object-none",
+        "object-scale-down",
+    ],
+    &["object-"],
+    &["p-"],
+    &["px-"],
+    &["py-"],
+    &["ps-"],
+    &["pe-"],
+    &["pt-"],
+    &["pr-"],
+    &["pb-"],
+    &["pl-"],
+    &[
+        "text-left",
+        "text-center",
+        "text-right",
+        "text-justify",
+        "text-start",
+        "text-end",
+    ],
+    &["indent-"],
+    &["align-"],
+    &["font-sans", "font-serif", "font-mono"],
+    &[
+        "text-xs",
+        "text-sm",
+        "text-base",
+        "text-lg",
+        "text-xl",
+        "text-2xl",
+        "text-3xl",
+        "text-4xl",
+        "text-5xl",
+        "text-6xl",
+        "text-7xl",
+        "text-8xl",
+        "text-9xl",
+    ],
+    &["leading-"],
+    &[
+        "font-thin",
+        "font-extralight",
+        "font-light",
+        "font-normal",
+        "font-medium",
+        "font-semibold",
+        "font-bold",
+        "font-extrabold",
+        "font-black",
+    ],
+    &["tracking-"],
+    &["text-wrap", "text-nowrap", "text-balance", "text-pretty"],
+    &["wrap-"],
+    &["break-normal", "break-words", "break-all", "break-keep"],
+    &["text-ellipsis", "text-clip"],
+    &["hyphens-"],
+    &["whitespace-"],
+    &["text-"],
+    &["uppercase", "lowercase", "capitalize", "normal-case"],
+    &["italic", "not-italic"],
+    &["font-stretch-"],
+    &[
+        "normal-nums",
+        "ordinal",
+        "slashed-zero",
+        "lining-nums",
+        "oldstyle-nums",
+        "proportional-nums",
+        "tabular-nums",
+        "diagonal-fractions",
+        "stacked-fractions",
+    ],
+    &["underline", "overline", "line-through", "no-underline"],
+    &["decoration-"],
+    &["underline-offset-"],
+    &["antialiased", "subpixel-antialiased"],
+    &["placeholder-"],
+    &["caret-"],
+    &["accent-"],
+    &["scheme-"],
+    &["opacity-"],
+    &["bg-blend-"],
+    &["mix-blend-"],
+    &["shadow", "shadow-"],
+    &["inset-shadow-"],
+    &[
+        "ring",
+        "ring-0",
+        "ring-1",
+        "ring-2",
+        "ring-4",
+        "ring-8",
+        "ring-inset",
+    ],
+    &["ring-"],
+    &["inset-ring", "inset-ring-"],
+    &["ring-offset-"],
+    &[
+        "outline",
+        "outline-hidden",
+        "outline-none",
+        "outline-dashed",
+        "outline-dotted",
+        "outline-double",
+        "outline-0",
+        "outline-1",
+        "outline-2",
+        "outline-4",
+        "outline-8",
+    ],
+    &["outline-offset-"],
+    &["outline-"],
+    &[
+        "filter",
+        "filter-none",
+        "blur",
+        "blur-",
+        "brightness-",
+        "contrast-",
+        "drop-shadow",
+        "drop-shadow-",
+        "grayscale",
+        "grayscale-",
+        "hue-rotate-",
+        "invert",
+        "invert-",
+        "saturate-",
+        "sepia",
+        "sepia-",
+    ],
+    &["backdrop-"],
+    &["transition", "transition-"],
+    &["delay-"],
+    &["duration-"],
+    &["ease-"],
+    &["will-change-"],
+    &["contain-"],
+    &["content-none"],
+    &["forced-color-adjust-"],
+];
+
+/// Returns the index of the property group the class belongs to, negative values are grouped
+/// with their positive counterparts and unknown classes are sorted last
+fn tailwind_v4_property_position(class: &str) -> usize {
+    let class = class.strip_prefix('-').unwrap_or(class);
+
+    TAILWIND_V4_PROPERTY_ORDER
+        .iter()
+        .enumerate()
+        .flat_map(|(position, patterns)| patterns.iter().map(move |pattern| (position, pattern)))
+        .filter_map(|(position, pattern)| {
+            if pattern.ends_with('-') {
+                class
+                    .starts_with(pattern)
+                    .then_some((pattern.len(), position))
+            } else {
+                (class == *pattern).then_some((usize::MAX, position))
+            }
+        })
+        .min_by_key(|&(length, position)| (std::cmp::Reverse(length), position))
+        .map(|(_length, position)| position)
+        .unwrap_or(TAILWIND_V4_PROPERTY_ORDER.len())
+}
+
+/// Utilities that were added or renamed in Tailwind v4 and are not part of `SORTER`
+fn tailwind_v4_additions() -> Vec<String> {
+    const SPACING: &[&str] = &[
+        "0", "0.5", "1", "1.5", "2", "2.5", "3", "3.5", "4", "5", "6", "7", "8", "9", "10", "11",
+        "12", "14", "16", "20", "24", "28", "32", "36", "40", "44", "48", "52", "56", "60", "64",
+        "72", "80", "96", "px", "auto", "full",
+    ];
+
+    let spacing = ["size", "start", "end", "ms", "me", "ps", "pe", "basis"]
+        .into_iter()
+        .flat_map(|prefix| SPACING.iter().map(move |value| format!("{prefix}-{value}")));
+
+    let line_clamp = ["1", "2", "3", "4", "5", "6", "none"]
+        .into_iter()
+        .map(|value| format!("line-clamp-{value}"));
+
+    let fixed = [
+        "box-border",
+        "inline-table",
+        "list-item",
+        "isolate",
+        "isolation-auto",
+        "field-sizing-content",
+        "field-sizing-fixed",
+        "shrink",
+        "shrink-0",
+        "grow",
+        "grow-0",
+        "truncate",
+        "text-ellipsis",
+        "text-clip",
+        "text-wrap",
+        "text-nowrap",
+        "text-balance",
+        "text-pretty",
+        "text-start",
+        "text-end",
+        "wrap-break-word",
+        "wrap-anywhere",
+        "wrap-normal",
+        "break-keep",
+        "overline",
+        "outline-hidden",
+        "outline",
+        "rounded-xs",
+        "shadow-2xs",
+        "shadow-xs",
+        "inset-shadow-2xs",
+        "inset-shadow-xs",
+        "inset-shadow-sm",
+        "inset-shadow-none",
+        "inset-ring",
+        "blur-xs",
+        "drop-shadow-xs",
+        "bg-linear-to-t",
+        "bg-linear-to-tr",
+        "bg-linear-to-r",
+        "bg-linear-to-br",
+        "bg-linear-to-b",
+        "bg-linear-to-bl",
+        "bg-linear-to-l",
+        "bg-linear-to-tl",
+        "bg-radial",
+        "bg-conic",
+        "scheme-normal",
+        "scheme-dark",
+        "scheme-light",
+        "scheme-light-dark",
+        "scroll-auto",
+        "scroll-smooth",
+        "transform-cpu",
+        "transition-discrete",
+        "transition-normal",
+        "forced-color-adjust-auto",
+        "forced-color-adjust-none",
+    ]
+    .into_iter()
+    .map(String::from);
+
+    spacing.chain(line_clamp).chain(fixed).collect()
+}
@@ -0,0 +1,96 @@
+//! Git helpers for `--staged`, used to run RustyWind as a pre-commit hook, and for `--since`
+
+use color_eyre::Help;
+use eyre::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Returns the added, copied and modified files that are staged in the repository containing `dir`.
+/// Files that also have unstaged changes are skipped, sorting them would mix the unstaged changes
+/// into the commit
+pub fn get_staged_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let root = get_root(dir)?;
+
+    let staged = run_git(
+        dir,
+        &["diff", "--cached", "--name-only", "--diff-filter=ACM"],
+    )?;
+    let unstaged = run_git(dir, &["diff", "--name-only"])?;
+    let unstaged: HashSet<&str> = unstaged.lines().collect();
+
+    Ok(staged
+        .lines()
+        .filter(|file| {
+            let is_partially_staged = unstaged.contains(file);
+
+            if is_partially_staged {
+                tracing::warn!("{file} has unstaged changes, it will not be sorted");
+            }
+
+            !is_partially_staged
+        })
+        .map(|file| root.join(file))
+        .collect())
+}
+
+/// Returns the files added, copied, modified or renamed on the current branch since it diverged
+/// from `git_ref`, the same as `git diff <git_ref>...HEAD`. The uncommitted changes are not included
+pub fn get_changed_files_since(dir: &Path, git_ref: &str) -> Result<Vec<PathBuf>> {
+    let root = get_root(dir)?;
+
+    let changed = run_git(
+        dir,
+        &[
+            "diff",
+            "--name-only",
+            "--diff-filter=ACMR",
+            &format!("{git_ref}...HEAD"),
+        ],
+    )
+    .with_suggestion(|| {
+        format!("Make sure {git_ref} is a branch, tag or commit, ex: --since main")
+    })?;
+
+    Ok(changed.lines().map(|file| root.join(file)).collect())
+}
+
+/// Stages the files again after they were rewritten
+pub fn stage_files(dir: &Path, files: &[&Path]) -> Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let mut args = vec!["add", "--"];
+    args.extend(files.iter().filter_map(|file| file.to_str()));
+
+    run_git(dir, &args).map(|_| ())
+}
+
+/// The root of the repository containing `dir`, the paths printed by git are relative to it
+fn get_root(dir: &Path) -> Result<PathBuf> {
+    let root = run_git(dir, &["rev-parse", "--show-toplevel"])
+        .with_suggestion(|| "--staged and --since have to be run inside of a git repository")?;
+
+    Ok(PathBuf::from(root.trim()))
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .wrap_err("Unable to run git")
+        .with_suggestion(|| "Make sure git is installed and in your PATH")?;
+
+    if !output.status.success() {
+        return Err(eyre::eyre!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,45 @@
+//! The starter config file written by `--init`
+
+use color_eyre::Help;
+use eyre::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the config file written by `--init`, it is found by the config auto-discovery
+pub const CONFIG_TEMPLATE_FILE_NAME: &str = "rustywind.json";
+
+/// JSON has no comments, the keys starting with `//` are ignored when the config is parsed. The
+/// template doesn't change the default behavior until it's edited
+pub const CONFIG_TEMPLATE: &str = r#"{
+  "//": "RustyWind config, see https://github.com/avencera/rustywind#usage",
+
+  "//sortOrder": "The classes in prepend and append are sorted before and after the default sort order, an array of classes instead of this object replaces the default sort order",
+  "sortOrder": {
+    "prepend": [],
+    "append": []
+  },
+
+  "//customRegex": "Finders for other syntaxes, the first capture group of class is sorted inside of the regions matched by container. Copy the examples below to use them, the class attributes are then only sorted with a preset, ex: --clsx",
+  "customRegex": [],
+  "//customRegexExamples": [
+    { "container": "tw\\(.*?\\)", "class": "\"([^\"]*)\"" },
+    { "container": "class=\\(\"[^\"]*\"", "class": "\"([^\"]*)\"" }
+  ]
+}
+"#;
+
+/// Writes the `CONFIG_TEMPLATE` into the directory, an existing config file is only overwritten
+/// with `force`. Returns the path of the written file
+pub fn write_config_template(dir: &Path, force: bool) -> Result<PathBuf> {
+    let path = dir.join(CONFIG_TEMPLATE_FILE_NAME);
+
+    if path.exists() && !force {
+        return Err(eyre::eyre!("{} already exists", path.display()))
+            .with_suggestion(|| "Run with --force to overwrite it");
+    }
+
+    fs::write(&path, CONFIG_TEMPLATE)
+        .wrap_err_with(|| format!("Unable to write the config file {}", path.display()))?;
+
+    Ok(path)
+}
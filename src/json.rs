@@ -0,0 +1,219 @@
+//! Sorts the classes of the string values of JSON files for `--json-field`, ex: design tokens
+//! with `{"classes": "flex px-2"}`. Only the strings at the field are replaced, so the rest of the
+//! file keeps its formatting
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::options::Options;
+use crate::utils::{self, ClassChange};
+
+/// Whether the JSON has a string at the field, invalid JSON has none
+pub fn has_json_field(file_contents: &str, json_field: &str) -> bool {
+    !find_json_field(file_contents, json_field).is_empty()
+}
+
+/// Sorts the classes of every string at the field, a field is a key, ex: `classes`, or a dotted
+/// path of keys, ex: `button.classes`, matched against the end of the path of the string. `*`
+/// matches any key and arrays are walked through, so the strings of an array at the field are
+/// sorted too. Invalid JSON is returned as is
+pub fn sort_json_field<'a>(
+    file_contents: &'a str,
+    json_field: &str,
+    options: &Options,
+) -> (Cow<'a, str>, Vec<ClassChange>) {
+    let mut changes = vec![];
+    let mut sorted_contents = String::with_capacity(file_contents.len());
+    let mut last_end = 0;
+    let mut positions = utils::Positions::new(file_contents);
+
+    for range in find_json_field(file_contents, json_field) {
+        let classes: String = match serde_json::from_str(&file_contents[range.clone()]) {
+            Ok(classes) => classes,
+            Err(_) => continue,
+        };
+
+        let sorted_classes = utils::sort_classes(&classes, options);
+        if classes == sorted_classes {
+            continue;
+        }
+
+        sorted_contents.push_str(&file_contents[last_end..range.start]);
+        sorted_contents.push_str(&serde_json::Value::String(sorted_classes.clone()).to_string());
+        last_end = range.end;
+
+        let (line, column) = positions.at(range.start);
+        changes.push(ClassChange {
+            before: classes,
+            after: sorted_classes,
+            line,
+            column,
+        });
+    }
+
+    if changes.is_empty() {
+        return (Cow::Borrowed(file_contents), changes);
+    }
+
+    sorted_contents.push_str(&file_contents[last_end..]);
+    (Cow::Owned(sorted_contents), changes)
+}
+
+/// The classes at the field that are not in the sorter, see `utils::find_unknown_classes`
+pub fn find_unknown_classes(
+    file_contents: &str,
+    json_field: &str,
+    options: &Options,
+) -> Vec<String> {
+    find_json_field(file_contents, json_field)
+        .into_iter()
+        .filter_map(|range| serde_json::from_str::<String>(&file_contents[range]).ok())
+        .flat_map(|classes| utils::find_unknown_classes_in(&classes, options))
+        .collect()
+}
+
+/// The ranges of the string literals at the field, including their quotes
+fn find_json_field(file_contents: &str, json_field: &str) -> Vec<Range<usize>> {
+    let contents = utils::strip_bom(file_contents);
+    let bom_len = file_contents.len() - contents.len();
+
+    if serde_json::from_str::<serde::de::IgnoredAny>(contents).is_err() {
+        tracing::debug!("not valid JSON, will not sort");
+        return vec![];
+    }
+
+    let field: Vec<&str> = json_field.split('.').collect();
+    let mut scanner = Scanner {
+        contents: contents.as_bytes(),
+        position: 0,
+    };
+
+    let mut found = vec![];
+    scanner.value(&mut vec![], &field, &mut found);
+
+    found
+        .into_iter()
+        .map(|range| range.start + bom_len..range.end + bom_len)
+        .collect()
+}
+
+/// Walks JSON that was already validated by `serde_json`, keeping track of the path of keys
+struct Scanner<'a> {
+    contents: &'a [u8],
+    position: usize,
+}
+
+impl Scanner<'_> {
+    fn value(&mut self, path: &mut Vec<String>, field: &[&str], found: &mut Vec<Range<usize>>) {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(b'{') => {
+                self.position += 1;
+
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some(b'"') {
+                        // `}` of an empty object
+                        self.position += 1;
+                        return;
+                    }
+
+                    let key = self.string();
+                    let key = std::str::from_utf8(&self.contents[key])
+                        .ok()
+                        .and_then(|key| serde_json::from_str(key).ok())
+                        .unwrap_or_default();
+
+                    self.skip_whitespace();
+                    // `:`
+                    self.position += 1;
+
+                    path.push(key);
+                    self.value(path, field, found);
+                    path.pop();
+
+                    self.skip_whitespace();
+                    // `,` or `}`
+                    let separator = self.peek();
+                    self.position += 1;
+                    if separator != Some(b',') {
+                        return;
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.position += 1;
+
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() == Some(b']') {
+                        self.position += 1;
+                        return;
+                    }
+
+                    self.value(path, field, found);
+
+                    self.skip_whitespace();
+                    // `,` or `]`
+                    let separator = self.peek();
+                    self.position += 1;
+                    if separator != Some(b',') {
+                        return;
+                    }
+                }
+            }
+            Some(b'"') => {
+                let string = self.string();
+                if is_at_field(path, field) {
+                    found.push(string);
+                }
+            }
+            // numbers, booleans and null
+            _ => {
+                while let Some(byte) = self.peek() {
+                    if matches!(byte, b',' | b']' | b'}') || byte.is_ascii_whitespace() {
+                        break;
+                    }
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the range of the string starting at the current position, including its quotes
+    fn string(&mut self) -> Range<usize> {
+        let start = self.position;
+        self.position += 1;
+
+        while let Some(byte) = self.peek() {
+            self.position += 1;
+
+            match byte {
+                b'\\' => self.position += 1,
+                b'"' => break,
+                _ => (),
+            }
+        }
+
+        start..self.position
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.contents.get(self.position).copied()
+    }
+}
+
+fn is_at_field(path: &[String], field: &[&str]) -> bool {
+    path.len() >= field.len()
+        && path[path.len() - field.len()..]
+            .iter()
+            .zip(field)
+            .all(|(key, field_key)| *field_key == "*" || key == field_key)
+}
@@ -0,0 +1,28 @@
+pub mod cache;
+pub mod cli;
+pub mod consts;
+pub mod defaults;
+pub mod git;
+pub mod init;
+pub mod json;
+pub mod options;
+pub mod presets;
+pub mod utils;
+
+// src/tests.rs has its own nested `tests` module
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
+
+use std::borrow::Cow;
+
+pub use options::{
+    FileRegex, FinderRegex, Options, OptionsBuilder, Outcome, RegexPair, Sorter, WriteMode,
+};
+pub use utils::{has_classes, sort_file_contents};
+
+/// Sorts all the classes found in `input` using the finder regex and sorter from `options`,
+/// returns the input as is when nothing had to be sorted
+pub fn sort_classes_in_str<'a>(input: &'a str, options: &Options) -> Cow<'a, str> {
+    sort_file_contents(input, options)
+}
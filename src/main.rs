@@ -1,105 +1,121 @@
-pub mod consts;
-pub mod defaults;
-pub mod options;
-pub mod utils;
-
 use clap::Parser;
-use eyre::Result;
-use indoc::indoc;
+use eyre::{Context, Result};
+use itertools::Itertools;
+use notify::{EventKind, RecursiveMode, Watcher};
 use once_cell::sync::Lazy;
-use options::{Options, WriteMode};
 use rayon::prelude::*;
-use std::collections::HashSet;
+use rustywind::cache::CACHE_FILE_NAME;
+use rustywind::cli::{Cli, LogLevel};
+use rustywind::options::{FinderRegex, Options, Outcome, Sorter, TailwindVersion, WriteMode};
+use rustywind::utils::{self, ClassChange};
+use rustywind::{git, init};
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+use std::collections::BTreeSet;
 use std::fs;
+use std::io::{self, IsTerminal, Write};
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
 
 static EXIT_ERROR: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(false));
 
-#[derive(Parser, Debug)]
-#[clap(name = "RustyWind", author, version, about, long_about = None)]
-#[clap(args_override_self = true, arg_required_else_help = true)]
-#[clap(override_usage = indoc!("
-Run rustywind with a path to get a list of files that will be changed
-      rustywind . --dry-run
-
-    If you want to reorganize all classes in place, and change the files run with the `--write` flag
-      rustywind --write .
-
-    To print only the file names that would be changed run with the `--check-formatted` flag
-      rustywind --check-formatted .
-
-    If you want to run it on your STDIN, you can do:
-      echo \"<FILE CONTENTS>\" | rustywind --stdin
-                 
-    rustywind [FLAGS] <PATH>"))]
-pub struct Cli {
-    #[clap(
-        name = "file-or-dir",
-        help = "A file or directory to run on",
-        value_name = "PATH",
-        required_unless_present = "stdin"
-    )]
-    file_or_dir: Vec<String>,
-
-    #[clap(
-        long,
-        help = "Uses stdin instead of a file or folder",
-        conflicts_with_all = &["write", "file-or-dir", "dry-run"],
-        required_unless_present = "file-or-dir",
-    )]
-    stdin: bool,
-
-    #[clap(
-        long,
-        help = "Changes the files in place with the reorganized classes",
-        conflicts_with_all = &["stdin", "dry-run", "check-formatted"],
-    )]
-    write: bool,
-
-    #[clap(
-        long,
-        help = "Prints out the new file content with the sorted classes to the terminal",
-        conflicts_with_all = &["stdin", "write", "check-formatted"]
-    )]
-    dry_run: bool,
-
-    #[clap(
-        long,
-        help = "Checks if the files are already formatted, exits with 1 if not formatted",
-        conflicts_with_all = &["stdin", "write", "dry-run"]
-
-    )]
-    check_formatted: bool,
-
-    #[clap(long, help = "When set, RustyWind will not delete duplicated classes")]
-    allow_duplicates: bool,
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
 
-    #[clap(
-        long,
-        help = "When set, RustyWind will use the config file to derive configurations. \
-        The config file current only supports json with one property sortOrder, \
-        e.g. { \"sortOrder\": [\"class1\", ...] }"
-    )]
-    config_file: Option<String>,
+fn main() -> Result<()> {
+    let cli = Cli::parse();
 
-    #[clap(long, help = "When set, RustyWind will ignore this list of files")]
-    ignored_files: Option<Vec<String>>,
+    // the logs go to stderr, so they never mix with the sorted output
+    let log_level = match (cli.log_level, cli.quiet, cli.verbose) {
+        (Some(log_level), _, _) => Some(log_level),
+        (None, true, _) => Some(LogLevel::Error),
+        (None, false, true) => Some(LogLevel::Debug),
+        (None, false, false) => None,
+    };
+
+    let log_filter = match log_level {
+        Some(log_level) => EnvFilter::default().add_directive(LevelFilter::from(log_level).into()),
+        None => EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| EnvFilter::default().add_directive(LevelFilter::WARN.into())),
+    };
+
+    tracing_subscriber::fmt()
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .with_env_filter(log_filter)
+        .without_time()
+        .init();
 
-    #[clap(long, help = "Uses a custom regex instead of default one")]
-    custom_regex: Option<String>,
-}
-
-fn main() -> Result<()> {
-    env_logger::init();
     color_eyre::install()?;
 
-    let cli = Cli::parse();
+    if let Some(jobs) = cli.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()?;
+    }
+
+    if cli.init {
+        let path = init::write_config_template(&std::env::current_dir()?, cli.force)?;
+        println!("wrote {}", path.display());
+        return Ok(());
+    }
+
+    let (print_sort_order, print_config) = (cli.print_sort_order, cli.print_config);
+    let (count, list_files) = (cli.count, cli.list_files);
+    let dump_unknown_classes = cli.dump_unknown_classes;
     let options = Options::new_from_cli(cli)?;
 
+    if print_sort_order {
+        // written without println, so piping it into head doesn't panic once the pipe is closed
+        let mut stdout = io::stdout().lock();
+        for class in options.sort_order() {
+            if writeln!(stdout, "{class}").is_err() {
+                break;
+            }
+        }
+
+        return Ok(());
+    }
+
+    if print_config {
+        let report = ConfigReport::new(&options);
+
+        match &options.write_mode {
+            _ if options.json_plan || options.json_check => {
+                println!("{}", serde_json::to_string_pretty(&report)?)
+            }
+            WriteMode::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            _ => report.print(),
+        }
+
+        return Ok(());
+    }
+
+    if count {
+        print_files_with_classes(&options, list_files);
+        return Ok(());
+    }
+
+    if dump_unknown_classes {
+        print_unknown_classes(&options);
+        return Ok(());
+    }
+
+    if let Some(output) = &options.output {
+        return write_to_output(output, &options);
+    }
+
     match &options.write_mode {
+        _ if options.quiet || options.json_plan || options.json_check => (),
         WriteMode::ToStdOut => (),
         WriteMode::DryRun => println!(
             "\ndry run mode activated: here is a list of files that \
@@ -114,56 +130,653 @@ fn main() -> Result<()> {
             "\nprinting file contents to console, run with --write to save changes to files:"
         ),
         WriteMode::CheckFormatted => println!("\nonly printing changed files"),
+        WriteMode::Json | WriteMode::Diff => (),
     }
 
     if let WriteMode::ToStdOut = &options.write_mode {
         let contents = options.stdin.clone().unwrap_or_default();
 
         if utils::has_classes(&contents, &options) {
-            let sorted_content = utils::sort_file_contents(&contents, &options);
+            let (sorted_content, changes) =
+                utils::sort_file_contents_with_changes(&contents, &options);
             print!("{sorted_content}");
+            print_duplicate_warnings("STDIN", &changes, &options);
         } else {
             print!("{contents}");
-            eprint!("[WARN] No classes were found in STDIN");
+
+            if !options.quiet {
+                eprint!("[WARN] No classes were found in STDIN");
+            }
         }
+    } else if let (WriteMode::Json, Some(contents)) = (&options.write_mode, &options.stdin) {
+        let report = JsonReport::new(None, contents, &options);
+        println!("{}", serde_json::to_string_pretty(&report)?);
     } else {
-        options
+        let mut sorted_files: Vec<SortedFile> = options
             .search_paths
             .par_iter()
-            .for_each(|file_path| run_on_file_paths(file_path, &options));
+            .filter_map(|file_path| read_and_sort_file(file_path, &options))
+            .collect();
+
+        // files are processed in parallel, sort them so the output is always in the same order
+        sorted_files.sort_by(|a, b| a.path.cmp(&b.path));
 
-        if EXIT_ERROR.load(Ordering::Relaxed) {
-            std::process::exit(1);
+        // the files that are not approved are dropped, so they are not written, restaged or counted
+        if let (WriteMode::ToFile, true) = (&options.write_mode, options.interactive) {
+            sorted_files = approve_changed_files(sorted_files, &options)?;
         }
+
+        if let WriteMode::Json = &options.write_mode {
+            let reports: Vec<JsonReport> = sorted_files
+                .iter()
+                .map(|sorted_file| JsonReport::from_sorted_file(sorted_file, &options))
+                .collect();
+
+            println!("{}", serde_json::to_string_pretty(&reports)?);
+        } else {
+            for sorted_file in &sorted_files {
+                run_on_sorted_file(sorted_file, &options);
+            }
+        }
+
+        if options.json_plan {
+            print_dry_run_plan(&sorted_files, &options)?;
+        }
+
+        if options.strict {
+            report_unmatched_class_attributes(&options);
+        }
+
+        if options.json_check {
+            print_check_formatted_report(&sorted_files, &options)?;
+        } else if let (WriteMode::CheckFormatted, false) = (&options.write_mode, options.quiet) {
+            let unformatted_files = sorted_files.iter().filter(|file| file.is_changed()).count();
+            print_check_formatted_summary(unformatted_files);
+        }
+
+        // the sorted and the written files were recorded in the cache, the other modes only read
+        // it, they don't write anything
+        if let (WriteMode::ToFile, Some(cache)) = (&options.write_mode, &options.cache) {
+            match cache.save(Path::new(CACHE_FILE_NAME)) {
+                Err(err) if !options.quiet => eprintln!("\n[WARN] {err}"),
+                _ => (),
+            }
+        }
+
+        if let (WriteMode::ToFile, true) = (&options.write_mode, options.restage) {
+            let changed_files: Vec<&Path> = sorted_files
+                .iter()
+                .filter(|file| file.is_changed())
+                .map(|file| file.path.as_path())
+                .collect();
+
+            git::stage_files(Path::new("."), &changed_files)?;
+        }
+
+        if options.stats {
+            Stats::new(options.search_paths.len(), &sorted_files).print(&options.write_mode);
+        }
+
+        // the first run is done like any other run, then only the changed files are sorted
+        if options.watch {
+            return watch(&options);
+        }
+    }
+
+    if EXIT_ERROR.load(Ordering::Relaxed) {
+        std::process::exit(1);
     }
 
     Ok(())
 }
 
-fn run_on_file_paths(file_path: &Path, options: &Options) {
-    // if the file is in the ignored_files list return early
-    if should_ignore_current_file(&options.ignored_files, file_path) {
-        log::debug!("file path {file_path:#?} found in ignored_files, will not sort");
-        return;
+/// Sorts the stdin or the single input file and writes it to `--output`, the output is written
+/// even if nothing was sorted, so it always ends up with the sorted contents
+fn write_to_output(output: &Path, options: &Options) -> Result<()> {
+    let (contents, path) = match (&options.stdin, options.starting_paths.first()) {
+        (Some(contents), _) => (contents.clone(), None),
+        (None, Some(path)) => (
+            fs::read_to_string(path)
+                .wrap_err_with(|| format!("Unable to read the file {}", path.display()))?,
+            Some(path),
+        ),
+        (None, None) => return Ok(()),
+    };
+
+    let (sorted_contents, _) = match path {
+        Some(path) => options.sort_contents(path, &contents),
+        None => utils::sort_file_contents_with_changes(&contents, options),
+    };
+    options.check_fix_only(output)?;
+
+    fs::write(output, sorted_contents.as_bytes())
+        .wrap_err_with(|| format!("Unable to write to {}", output.display()))
+}
+
+/// How long to wait for more changes before sorting the changed files
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+enum WatchMessage {
+    Changed(notify::Result<notify::Event>),
+    Exit,
+}
+
+/// Watches the starting paths and sorts the files that change until Ctrl-C is pressed,
+/// the changes are debounced and only the changed files are sorted again
+fn watch(options: &Options) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+
+    let watch_sender = sender.clone();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = watch_sender.send(WatchMessage::Changed(event));
+    })?;
+
+    for starting_path in &options.starting_paths {
+        watcher.watch(starting_path, RecursiveMode::Recursive)?;
     }
 
-    match fs::read_to_string(file_path) {
-        Ok(contents) => {
-            if utils::has_classes(&contents, options) {
-                let sorted_content = utils::sort_file_contents(&contents, options);
+    ctrlc::set_handler(move || {
+        let _ = sender.send(WatchMessage::Exit);
+    })?;
 
-                match &options.write_mode {
-                    WriteMode::ToStdOut => (),
-                    WriteMode::DryRun => print_file_name(file_path, options),
-                    WriteMode::ToFile => write_to_file(file_path, &sorted_content, options),
-                    WriteMode::ToConsole => print_file_contents(&sorted_content),
-                    WriteMode::CheckFormatted => {
-                        print_changed_files(file_path, &sorted_content, &contents, options);
-                    }
+    if !options.quiet {
+        println!("\nwatching for changes, press Ctrl-C to exit");
+    }
+
+    let mut changed_paths = BTreeSet::new();
+
+    loop {
+        // wait for the first change, then keep collecting until the changes settle down
+        let message = if changed_paths.is_empty() {
+            receiver.recv().ok()
+        } else {
+            match receiver.recv_timeout(WATCH_DEBOUNCE) {
+                Ok(message) => Some(message),
+                Err(RecvTimeoutError::Timeout) => {
+                    sort_changed_files(std::mem::take(&mut changed_paths), options);
+                    continue;
                 }
+                Err(RecvTimeoutError::Disconnected) => None,
+            }
+        };
+
+        match message {
+            Some(WatchMessage::Changed(Ok(event))) => {
+                if let EventKind::Create(_) | EventKind::Modify(_) = event.kind {
+                    changed_paths.extend(event.paths);
+                }
+            }
+            Some(WatchMessage::Changed(Err(err))) if !options.quiet => eprintln!("\n[WARN] {err}"),
+            Some(WatchMessage::Changed(Err(_))) => (),
+            Some(WatchMessage::Exit) | None => break,
+        }
+    }
+
+    if !options.quiet {
+        println!("\nstopped watching");
+    }
+    Ok(())
+}
+
+fn sort_changed_files(changed_paths: BTreeSet<PathBuf>, options: &Options) {
+    let mut sorted_files = vec![];
+
+    for path in changed_paths {
+        if !options.is_search_path(&path) {
+            continue;
+        }
+
+        // writing the sorted file triggers another change, it is already sorted so it is skipped
+        if let Some(sorted_file) = read_and_sort_file(&path, options) {
+            if sorted_file.is_changed() {
+                run_on_sorted_file(&sorted_file, options);
+                sorted_files.push(sorted_file);
             }
         }
-        Err(_error) => (),
+    }
+
+    if options.json_plan && !sorted_files.is_empty() {
+        if let Err(err) = print_dry_run_plan(&sorted_files, options) {
+            eprintln!("\nError: {err}");
+        }
+    }
+}
+
+/// A file that has classes, with its contents before and after sorting
+struct SortedFile {
+    path: PathBuf,
+    contents: String,
+    sorted_contents: String,
+    changes: Vec<ClassChange>,
+}
+
+impl SortedFile {
+    fn is_changed(&self) -> bool {
+        self.sorted_contents != self.contents
+    }
+}
+
+/// The summary printed with `--stats`
+struct Stats {
+    files_scanned: usize,
+    files_with_classes: usize,
+    files_changed: usize,
+    classes_sorted: usize,
+    duplicates_removed: usize,
+}
+
+impl Stats {
+    fn new(files_scanned: usize, sorted_files: &[SortedFile]) -> Self {
+        let changes = sorted_files.iter().flat_map(|file| &file.changes);
+
+        Stats {
+            files_scanned,
+            files_with_classes: sorted_files.len(),
+            files_changed: sorted_files.iter().filter(|file| file.is_changed()).count(),
+            classes_sorted: changes.clone().count(),
+            duplicates_removed: changes.map(ClassChange::removed_duplicates).sum(),
+        }
+    }
+
+    /// Printed to stderr so the output of the other modes can still be piped
+    fn print(&self, write_mode: &WriteMode) {
+        let (changed, sorted, removed) = match write_mode {
+            WriteMode::ToFile => ("changed", "sorted", "removed"),
+            _ => ("would be changed", "would be sorted", "would be removed"),
+        };
+
+        eprintln!("\nfiles scanned: {}", self.files_scanned);
+        eprintln!("files with classes: {}", self.files_with_classes);
+        eprintln!("files {changed}: {}", self.files_changed);
+        eprintln!("class strings {sorted}: {}", self.classes_sorted);
+        eprintln!("duplicate classes {removed}: {}", self.duplicates_removed);
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport {
+    path: Option<String>,
+    changed: bool,
+    changes: Vec<ClassChange>,
+}
+
+impl JsonReport {
+    fn new(path: Option<String>, contents: &str, options: &Options) -> Self {
+        let (sorted_content, changes) = utils::sort_file_contents_with_changes(contents, options);
+
+        JsonReport {
+            path,
+            changed: sorted_content != contents,
+            changes,
+        }
+    }
+
+    fn from_sorted_file(sorted_file: &SortedFile, options: &Options) -> Self {
+        JsonReport {
+            path: Some(get_file_name(&sorted_file.path, &options.starting_paths)),
+            changed: sorted_file.is_changed(),
+            changes: sorted_file.changes.clone(),
+        }
+    }
+}
+
+/// A file that would be changed, with the class strings that would be rewritten, for
+/// `--dry-run --output-format json`
+#[derive(Serialize)]
+struct PlannedFile<'a> {
+    path: String,
+    changes: &'a [ClassChange],
+}
+
+/// Prints the files that would be changed as a json array, the files that are already sorted are
+/// left out
+fn print_dry_run_plan(sorted_files: &[SortedFile], options: &Options) -> Result<()> {
+    let plan: Vec<PlannedFile> = sorted_files
+        .iter()
+        .filter(|sorted_file| sorted_file.is_changed())
+        .map(|sorted_file| PlannedFile {
+            path: get_file_name(&sorted_file.path, &options.starting_paths),
+            changes: &sorted_file.changes,
+        })
+        .collect();
+
+    println!("{}", serde_json::to_string_pretty(&plan)?);
+    Ok(())
+}
+
+/// The result of `--check-formatted --output-format json`, `formatted` is false when any of the
+/// `files` is not formatted
+#[derive(Serialize)]
+struct CheckFormattedReport {
+    formatted: bool,
+    files: Vec<String>,
+}
+
+fn print_check_formatted_report(sorted_files: &[SortedFile], options: &Options) -> Result<()> {
+    let files: Vec<String> = sorted_files
+        .iter()
+        .filter(|sorted_file| sorted_file.is_changed())
+        .map(|sorted_file| get_file_name(&sorted_file.path, &options.starting_paths))
+        .collect();
+
+    let report = CheckFormattedReport {
+        formatted: files.is_empty(),
+        files,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// The options in effect after merging the CLI flags and the config files, for `--print-config`
+#[derive(Serialize)]
+struct ConfigReport {
+    config_files: Vec<String>,
+    write_mode: &'static str,
+    finders: Vec<String>,
+    file_finders: Vec<String>,
+    json_field: Option<String>,
+    sorter: String,
+    allow_duplicates: bool,
+    preserve_whitespace: bool,
+    cache: bool,
+    search_paths: usize,
+    ignored_files: Vec<String>,
+}
+
+impl ConfigReport {
+    fn new(options: &Options) -> Self {
+        let write_mode = match options.write_mode {
+            WriteMode::ToFile => "write",
+            WriteMode::DryRun => "dry-run",
+            WriteMode::ToConsole => "console",
+            WriteMode::ToStdOut => "stdout",
+            WriteMode::CheckFormatted => "check-formatted",
+            WriteMode::Json => "json",
+            WriteMode::Diff => "diff",
+        };
+
+        let tailwind_version = match options.tailwind_version {
+            TailwindVersion::V3 => 3,
+            TailwindVersion::V4 => 4,
+        };
+
+        let sorter = match &options.sorter {
+            Sorter::DefaultSorter => format!("default tailwind {tailwind_version} sort order"),
+            Sorter::CustomSorter(sorter) => {
+                format!("custom sort order of {} classes", sorter.len())
+            }
+        };
+
+        ConfigReport {
+            config_files: options
+                .config_files
+                .iter()
+                .map(|config_file| config_file.display().to_string())
+                .collect(),
+            write_mode,
+            finders: describe_finder(&options.regex),
+            file_finders: options
+                .file_regex
+                .iter()
+                .map(|file_regex| file_regex.glob.glob().to_string())
+                .collect(),
+            json_field: options.json_field.clone(),
+            sorter,
+            allow_duplicates: options.allow_duplicates,
+            preserve_whitespace: options.preserve_whitespace,
+            cache: options.cache.is_some(),
+            search_paths: options.search_paths.len(),
+            ignored_files: options.ignored_globs.clone(),
+        }
+    }
+
+    fn print(&self) {
+        let print_list = |name: &str, values: &[String]| {
+            if values.is_empty() {
+                println!("{name}: none");
+            } else {
+                println!("{name}:");
+                for value in values {
+                    println!("  {value}");
+                }
+            }
+        };
+
+        print_list("config files", &self.config_files);
+        println!("write mode: {}", self.write_mode);
+        print_list("finders", &self.finders);
+        print_list("file finders", &self.file_finders);
+        println!(
+            "json field: {}",
+            self.json_field.as_deref().unwrap_or("none")
+        );
+        println!("sorter: {}", self.sorter);
+        println!("allow duplicates: {}", self.allow_duplicates);
+        println!("preserve whitespace: {}", self.preserve_whitespace);
+        println!("cache: {}", self.cache);
+        println!("search paths: {}", self.search_paths);
+        print_list("ignored files", &self.ignored_files);
+    }
+}
+
+/// The regexes of the finder, a `customRegex` entry is printed as `container -> class`
+fn describe_finder(finder: &FinderRegex) -> Vec<String> {
+    match finder {
+        FinderRegex::DefaultRegex => vec!["default".to_string()],
+        FinderRegex::CustomRegex(regex) => vec![regex.to_string()],
+        FinderRegex::CustomRegexEntries(entries) => entries
+            .iter()
+            .map(|entry| format!("{} -> {}", entry.container_regex, entry.class_regex))
+            .collect(),
+    }
+}
+
+/// Shows the diff of every changed file and asks whether to write it, the unchanged files are kept
+/// as is. After a `q`, or once stdin is closed, the next changed files are not approved
+fn approve_changed_files(
+    sorted_files: Vec<SortedFile>,
+    options: &Options,
+) -> Result<Vec<SortedFile>> {
+    let mut approved_files = Vec::with_capacity(sorted_files.len());
+    let (mut approve_all, mut quit) = (false, false);
+
+    for sorted_file in sorted_files {
+        if !sorted_file.is_changed() || approve_all {
+            approved_files.push(sorted_file);
+            continue;
+        }
+
+        if quit {
+            continue;
+        }
+
+        let file_name = get_file_name(&sorted_file.path, &options.starting_paths);
+        print_diff(
+            &sorted_file.path,
+            &sorted_file.sorted_contents,
+            &sorted_file.contents,
+            options,
+        );
+
+        loop {
+            print!("Write {file_name}? [y/n/a/q] ");
+            io::stdout().flush()?;
+
+            let mut answer = String::new();
+            if io::stdin().read_line(&mut answer)? == 0 {
+                println!();
+                quit = true;
+                break;
+            }
+
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => approved_files.push(sorted_file),
+                "n" | "no" => (),
+                "a" | "all" => {
+                    approve_all = true;
+                    approved_files.push(sorted_file);
+                }
+                "q" | "quit" => quit = true,
+                _ => {
+                    println!("y: write it, n: skip it, a: write it and the next files, q: quit");
+                    continue;
+                }
+            }
+
+            break;
+        }
+    }
+
+    Ok(approved_files)
+}
+
+/// Reads and sorts the file, returns `None` if the file is skipped or can't be read
+fn read_and_sort_file(file_path: &Path, options: &Options) -> Option<SortedFile> {
+    let (contents, sorted_contents, changes) = match options.apply(file_path) {
+        Ok(Outcome::Changed {
+            before,
+            after,
+            changes,
+        }) => (before, after, changes),
+        Ok(Outcome::Unchanged { contents }) => (contents.clone(), contents, vec![]),
+        Ok(Outcome::Skipped) => return None,
+        Err(err) => {
+            tracing::debug!("unable to read {file_path:#?}, will not sort: {err}");
+            return None;
+        }
+    };
+
+    Some(SortedFile {
+        path: file_path.to_owned(),
+        contents,
+        sorted_contents,
+        changes,
+    })
+}
+
+fn run_on_sorted_file(sorted_file: &SortedFile, options: &Options) {
+    let SortedFile {
+        path,
+        contents,
+        sorted_contents,
+        changes,
+    } = sorted_file;
+
+    if options.warn_duplicates || options.fail_on_duplicate {
+        let file_name = get_file_name(path, &options.starting_paths);
+        print_duplicate_warnings(&file_name, changes, options);
+    }
+
+    match &options.write_mode {
+        WriteMode::ToStdOut | WriteMode::Json => (),
+        WriteMode::DryRun => {
+            if options.exit_code_on_change && sorted_file.is_changed() {
+                EXIT_ERROR.store(true, Ordering::Relaxed);
+            }
+
+            // the whole plan is printed once all the files are sorted
+            if options.json_plan {
+                return;
+            }
+
+            print_file_name(path, options);
+            print_class_changes(changes, options);
+        }
+        WriteMode::ToFile => write_to_file(path, sorted_contents, contents, options),
+        WriteMode::ToConsole if options.quiet => (),
+        WriteMode::ToConsole => print_file_contents(sorted_contents),
+        WriteMode::CheckFormatted => {
+            print_changed_files(path, sorted_contents, contents, options);
+        }
+        WriteMode::Diff if options.only_changed => print_class_diff(path, changes, options),
+        WriteMode::Diff => print_diff(path, sorted_contents, contents, options),
+    }
+}
+
+/// With `--strict`, warns about the class attributes that could not be sorted in any of the files
+fn report_unmatched_class_attributes(options: &Options) {
+    let mut unmatched: Vec<(&PathBuf, Vec<usize>)> = options
+        .search_paths
+        .par_iter()
+        .filter(|file_path| !options.is_ignored(file_path) && !options.is_too_large(file_path))
+        .filter_map(|file_path| {
+            let contents = fs::read_to_string(file_path).ok()?;
+            let regex = options.regex_for_path(file_path);
+            let lines = utils::find_unmatched_class_attributes(&contents, regex);
+
+            (!lines.is_empty()).then_some((file_path, lines))
+        })
+        .collect();
+
+    if unmatched.is_empty() {
+        return;
+    }
+
+    EXIT_ERROR.store(true, Ordering::Relaxed);
+
+    if options.quiet {
+        return;
+    }
+
+    unmatched.sort();
+    for (file_path, lines) in unmatched {
+        let file_name = get_file_name(file_path, &options.starting_paths);
+
+        for line in lines {
+            eprintln!("[WARN] {file_name}:{line}: class attribute could not be sorted");
+        }
+    }
+}
+
+/// Printed to stderr with `--warn-duplicates`, so the duplicates can be removed from the source
+/// With `fail_on_duplicate` the duplicates are errors, they are printed even with `quiet`
+fn print_duplicate_warnings(name: &str, changes: &[ClassChange], options: &Options) {
+    if !options.fail_on_duplicate && (!options.warn_duplicates || options.quiet) {
+        return;
+    }
+
+    for change in changes {
+        let duplicated_classes = change.duplicated_classes();
+
+        if duplicated_classes.is_empty() {
+            continue;
+        }
+
+        if options.fail_on_duplicate {
+            EXIT_ERROR.store(true, Ordering::Relaxed);
+            eprintln!(
+                "[ERROR] {name}: duplicated classes {} in \"{}\"",
+                duplicated_classes.join(" "),
+                change.before
+            );
+        } else {
+            eprintln!(
+                "[WARN] {name}: removed duplicated classes {} from \"{}\"",
+                duplicated_classes.join(" "),
+                change.before
+            );
+        }
+    }
+}
+
+/// Prints every class string that would be reordered, under the name of its file
+fn print_class_changes(changes: &[ClassChange], options: &Options) {
+    if options.quiet {
+        return;
+    }
+
+    // multi-line class strings are printed on a single line
+    let single_line = |classes: &str| classes.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    for change in changes {
+        println!(
+            "      {}:{} {} -> {}",
+            change.line,
+            change.column,
+            single_line(&change.before),
+            single_line(&change.after)
+        );
     }
 }
 
@@ -178,40 +791,200 @@ fn print_changed_files(
             EXIT_ERROR.store(true, Ordering::Relaxed);
         }
 
-        if !should_ignore_current_file(&options.ignored_files, file_path) {
+        // with json_check the files are printed in the report once all the files are checked
+        if !options.quiet && !options.json_check {
             let file_name = get_file_name(file_path, &options.starting_paths);
             eprintln!("  * [UNFORMATTED FILE] {file_name}")
         }
     }
 }
 
-/// Return a boolean indicating whether the file should be ignored
-fn should_ignore_current_file(ignored_files: &HashSet<PathBuf>, current_file: &Path) -> bool {
-    if ignored_files.is_empty() {
-        // if the ignored_files is empty no need to do any more work
-        false
-    } else {
-        current_file
-            .canonicalize()
-            .map(|path| ignored_files.contains(&path))
-            .unwrap_or(false)
+/// Prints a unified diff between the original and the sorted contents, if there is any change
+fn print_diff(file_path: &Path, sorted_content: &str, original_content: &str, options: &Options) {
+    if sorted_content == original_content {
+        return;
+    }
+
+    let file_name = get_file_name(file_path, &options.starting_paths);
+    let diff = TextDiff::from_lines(original_content, sorted_content);
+    let mut unified_diff = diff.unified_diff();
+    unified_diff.header(&format!("a/{file_name}"), &format!("b/{file_name}"));
+
+    if !options.color {
+        print!("{unified_diff}");
+        return;
+    }
+
+    println!("{BOLD}--- a/{file_name}{RESET}");
+    println!("{BOLD}+++ b/{file_name}{RESET}");
+
+    for hunk in unified_diff.iter_hunks() {
+        println!("{CYAN}{}{RESET}", hunk.header());
+
+        for change in hunk.iter_changes() {
+            match change.tag() {
+                ChangeTag::Delete => print!("{RED}-{change}{RESET}"),
+                ChangeTag::Insert => print!("{GREEN}+{change}{RESET}"),
+                ChangeTag::Equal => print!(" {change}"),
+            }
+
+            if change.missing_newline() {
+                println!();
+            }
+        }
     }
 }
 
-fn write_to_file(file_path: &Path, sorted_contents: &str, options: &Options) {
-    match fs::write(file_path, sorted_contents.as_bytes()) {
-        Ok(_) => print_file_name(file_path, options),
-        Err(err) => {
-            eprintln!("\nError: {:?}", err);
-            eprintln!(
-                "Unable to to save file: {}",
-                get_file_name(file_path, &options.starting_paths)
-            );
+/// Prints the old and new class list of every changed class string, the classes that moved or
+/// were removed are highlighted with `--color`
+fn print_class_diff(file_path: &Path, changes: &[ClassChange], options: &Options) {
+    if changes.is_empty() {
+        return;
+    }
+
+    let file_name = get_file_name(file_path, &options.starting_paths);
+    let (bold, red, green, reset) = match options.color {
+        true => (BOLD, RED, GREEN, RESET),
+        false => ("", "", "", ""),
+    };
+
+    println!("{bold}--- a/{file_name}{reset}");
+    println!("{bold}+++ b/{file_name}{reset}");
+
+    for change in changes {
+        let before: Vec<&str> = utils::split_classes(&change.before).collect();
+        let after: Vec<&str> = utils::split_classes(&change.after).collect();
+
+        let mut old_line = vec![];
+        let mut new_line = vec![];
+
+        // the classes that are kept in order by the smallest diff are left as is
+        for class_change in TextDiff::from_slices(&before, &after).iter_all_changes() {
+            let class = class_change.value();
+
+            match class_change.tag() {
+                ChangeTag::Delete => old_line.push(format!("{red}{class}{reset}")),
+                ChangeTag::Insert => new_line.push(format!("{green}{class}{reset}")),
+                ChangeTag::Equal => {
+                    old_line.push(class.to_string());
+                    new_line.push(class.to_string());
+                }
+            }
+        }
+
+        println!("{red}-{reset}{}", old_line.join(" "));
+        println!("{green}+{reset}{}", new_line.join(" "));
+    }
+}
+
+/// Only checks if the files have classes, nothing is sorted
+fn print_files_with_classes(options: &Options, list_files: bool) {
+    // the search paths are sorted and the parallel iterator keeps their order
+    let files_with_classes: Vec<&PathBuf> = options
+        .search_paths
+        .par_iter()
+        .filter(|file_path| match options.has_classes(file_path) {
+            Ok(has_classes) => has_classes,
+            Err(err) => {
+                tracing::debug!("unable to read {file_path:#?}, will not count: {err}");
+                false
+            }
+        })
+        .collect();
+
+    if list_files {
+        for file_path in &files_with_classes {
+            println!("{}", get_file_name(file_path, &options.starting_paths));
         }
     }
+
+    println!(
+        "{} of {} files have classes",
+        files_with_classes.len(),
+        options.search_paths.len()
+    );
+}
+
+/// Prints the classes that are not in the sorter with how many times they are used, the most used
+/// first, nothing is sorted
+fn print_unknown_classes(options: &Options) {
+    let unknown_classes: Vec<String> = options
+        .search_paths
+        .par_iter()
+        .flat_map_iter(|file_path| match options.unknown_classes(file_path) {
+            Ok(unknown_classes) => unknown_classes,
+            Err(err) => {
+                tracing::debug!("unable to read {file_path:#?}, will not check: {err}");
+                vec![]
+            }
+        })
+        .collect();
+
+    let counts = unknown_classes.iter().counts();
+
+    for (class, count) in counts
+        .into_iter()
+        .sorted_by(|(a_class, a_count), (b_class, b_count)| {
+            b_count.cmp(a_count).then(a_class.cmp(b_class))
+        })
+    {
+        println!("{count:>7} {class}");
+    }
+}
+
+fn print_check_formatted_summary(unformatted_files: usize) {
+    match unformatted_files {
+        0 => eprintln!("\nall files are formatted"),
+        1 => eprintln!("\n1 file would be reformatted"),
+        count => eprintln!("\n{count} files would be reformatted"),
+    }
+}
+
+fn write_to_file(
+    file_path: &Path,
+    sorted_contents: &str,
+    original_contents: &str,
+    options: &Options,
+) {
+    // files that are already sorted are not written, so their modified time doesn't change
+    if sorted_contents == original_contents {
+        return;
+    }
+
+    if let Err(err) = options.check_fix_only(file_path) {
+        EXIT_ERROR.store(true, Ordering::Relaxed);
+        eprintln!("\nError: {err}");
+        return;
+    }
+
+    // the real file is written, and backed up next to it, instead of the symlink that was walked
+    let real_path = if options.walk_options.follow_symlinks {
+        match fs::canonicalize(file_path) {
+            Ok(real_path) => real_path,
+            Err(err) => {
+                eprintln!("\nError: {:?}", err);
+                eprintln!(
+                    "Unable to resolve the symlinks of {}, it was not changed",
+                    get_file_name(file_path, &options.starting_paths)
+                );
+                return;
+            }
+        }
+    } else {
+        file_path.to_owned()
+    };
+
+    match options.write_sorted_file(&real_path, sorted_contents, original_contents) {
+        Ok(()) => print_file_name(file_path, options),
+        Err(err) => eprintln!("\nError: {err:#}"),
+    }
 }
 
 fn print_file_name(file_path: &Path, options: &Options) {
+    if options.quiet {
+        return;
+    }
+
     println!("  * {}", get_file_name(file_path, &options.starting_paths));
 }
 
@@ -1,17 +1,59 @@
+use clap::ArgEnum;
 use color_eyre::Help;
 use eyre::{Context, Result};
-use ignore::WalkBuilder;
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use ignore::{WalkBuilder, WalkState};
 use itertools::Itertools;
 use regex::Regex;
 use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::ffi::OsStr;
 use std::fs;
 use std::io::Read;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Mutex;
 
-use crate::Cli;
+use crate::cache::{self, Cache, CACHE_FILE_NAME};
+use crate::cli::{Cli, OutputFormat};
+use crate::defaults::{CLASS_FUNCTIONS, RE_IGNORE_CASE, SORTER, SORTER_V4};
+use crate::utils::{self, ClassChange};
+use crate::{git, json, presets};
+
+/// Files matching the gitignore style patterns in this file are not walked,
+/// nested files apply to their own directory
+pub const IGNORE_FILE_NAME: &str = ".rustywindignore";
+
+/// The config files that are used when no `--config-file` is passed, the first one found in the
+/// directory of the starting path or any of its parents is used
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["rustywind.json", "rustywind.toml"];
+
+/// A `package.json` with a `rustywind` key is used as the config file, when there is no
+/// `CONFIG_FILE_NAMES` file in the same directory
+pub const PACKAGE_JSON: &str = "package.json";
+
+/// Separates the groups of `groupPrefixes` with one extra space
+pub const DEFAULT_GROUP_SEPARATOR: &str = "  ";
+
+#[cfg(test)]
+thread_local! {
+    /// The number of finders compiled on the thread, the tests check that a finder is compiled
+    /// once and not again for every file
+    pub(crate) static FINDER_COMPILATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Counts a compiled finder in `FINDER_COMPILATIONS`, does nothing outside of the tests
+pub(crate) fn count_finder_compilation() {
+    #[cfg(test)]
+    FINDER_COMPILATIONS.with(|compilations| compilations.set(compilations.get() + 1));
+}
+
+/// The width of a tab when measuring the lines of `--max-line-length`
+pub const DEFAULT_TAB_WIDTH: usize = 4;
 
 #[derive(Debug)]
 pub enum WriteMode {
@@ -20,12 +62,47 @@ pub enum WriteMode {
     ToConsole,
     ToStdOut,
     CheckFormatted,
+    Json,
+    Diff,
 }
 
 #[derive(Debug)]
 pub enum FinderRegex {
     DefaultRegex,
     CustomRegex(Regex),
+    CustomRegexEntries(Vec<RegexPair>),
+}
+
+/// The `container_regex` finds the region to work on (ex: a `clsx(...)` call), the `class_regex`
+/// is then run inside of that region and its first matching capture group is sorted. When that
+/// group is named `dotted`, it's sorted as a chain of `.` prefixed classes, ex: `.px-2.flex`
+#[derive(Debug)]
+pub struct RegexPair {
+    pub container_regex: Regex,
+    pub class_regex: Regex,
+}
+
+/// A finder that is only used for the files matching the glob, ex: `*.jsx`
+#[derive(Debug)]
+pub struct FileRegex {
+    pub glob: GlobMatcher,
+    pub regex: FinderRegex,
+}
+
+/// The result of sorting a file with `Options::apply`
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The file has classes that are already sorted, or was already sorted in a previous run with
+    /// the same contents (see `cache`)
+    Unchanged { contents: String },
+    /// The file has classes that are not sorted, `changes` has every class string that was reordered
+    Changed {
+        before: String,
+        after: String,
+        changes: Vec<ClassChange>,
+    },
+    /// The file is ignored or has no classes
+    Skipped,
 }
 
 #[derive(Debug)]
@@ -34,26 +111,761 @@ pub enum Sorter {
     CustomSorter(HashMap<String, usize>),
 }
 
-#[derive(Deserialize)]
+/// The Tailwind version of the default sort order
+#[derive(ArgEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TailwindVersion {
+    /// Uses `SORTER`
+    #[default]
+    #[clap(name = "3")]
+    V3,
+    /// Uses `SORTER_V4`
+    #[clap(name = "4")]
+    V4,
+}
+
+impl TailwindVersion {
+    pub fn default_sorter(&self) -> &'static HashMap<String, usize> {
+        match self {
+            TailwindVersion::V3 => &SORTER,
+            TailwindVersion::V4 => &SORTER_V4,
+        }
+    }
+}
+
+/// How to order the classes that are not found in the sorter
+#[derive(ArgEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CustomClassOrder {
+    /// Keeps the order they were written in
+    #[default]
+    Original,
+    Alphabetical,
+}
+
+/// Where the classes that are not found in the sorter are placed
+#[derive(ArgEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CustomClassPosition {
+    /// Before the tailwind and variant classes
+    First,
+    /// After the tailwind and variant classes
+    #[default]
+    Last,
+}
+
+/// Where the `ignored_classes` are placed, they are never reordered between themselves
+#[derive(ArgEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-struct ConfigFileContents {
-    sort_order: Vec<String>,
+pub enum IgnoredClassPosition {
+    /// Before all the other classes
+    #[default]
+    First,
+    /// After all the other classes
+    Last,
+    /// Where they were written, the other classes are sorted around them
+    Original,
+}
+
+/// What separates the classes of a class string
+#[derive(ArgEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ClassSeparator {
+    #[default]
+    Whitespace,
+    /// Whitespace and commas, ex: `"px-2", "flex"` in an array literal, the classes can be quoted
+    Comma,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ConfigFileContents {
+    pub(crate) sort_order: Option<SortOrder>,
+    pub(crate) extend_default: Option<bool>,
+    pub(crate) sort_custom: Option<CustomClassOrder>,
+    pub(crate) custom_class_position: Option<CustomClassPosition>,
+    pub(crate) preserve_custom_position: Option<bool>,
+    pub(crate) class_separator: Option<ClassSeparator>,
+    pub(crate) ignore_classes: Option<Vec<String>>,
+    pub(crate) ignored_class_position: Option<IgnoredClassPosition>,
+    pub(crate) custom_regex: Option<Vec<CustomRegexEntry>>,
+    pub(crate) file_regex: Option<BTreeMap<String, Vec<CustomRegexEntry>>>,
+    pub(crate) class_functions: Option<Vec<String>>,
+    pub(crate) variant_order: Option<Vec<String>>,
+    pub(crate) warn_duplicates: Option<bool>,
+    pub(crate) group_prefixes: Option<Vec<String>>,
+    pub(crate) group_separator: Option<String>,
+    pub(crate) pin_marker_classes: Option<bool>,
+}
+
+impl ConfigFileContents {
+    /// Merges a later config file into this one, the `customRegex` entries are appended, the
+    /// `fileRegex` globs are added (replacing the same globs) and every other option that is set
+    /// in the later config file replaces the earlier one, `sortOrder` included
+    pub(crate) fn merge(self, later: ConfigFileContents) -> ConfigFileContents {
+        let custom_regex = match (self.custom_regex, later.custom_regex) {
+            (Some(mut earlier), Some(later)) => {
+                earlier.extend(later);
+                Some(earlier)
+            }
+            (earlier, later) => later.or(earlier),
+        };
+
+        let file_regex = match (self.file_regex, later.file_regex) {
+            (Some(mut earlier), Some(later)) => {
+                earlier.extend(later);
+                Some(earlier)
+            }
+            (earlier, later) => later.or(earlier),
+        };
+
+        ConfigFileContents {
+            sort_order: later.sort_order.or(self.sort_order),
+            extend_default: later.extend_default.or(self.extend_default),
+            sort_custom: later.sort_custom.or(self.sort_custom),
+            custom_class_position: later.custom_class_position.or(self.custom_class_position),
+            preserve_custom_position: later
+                .preserve_custom_position
+                .or(self.preserve_custom_position),
+            class_separator: later.class_separator.or(self.class_separator),
+            ignore_classes: later.ignore_classes.or(self.ignore_classes),
+            ignored_class_position: later.ignored_class_position.or(self.ignored_class_position),
+            custom_regex,
+            file_regex,
+            class_functions: later.class_functions.or(self.class_functions),
+            variant_order: later.variant_order.or(self.variant_order),
+            warn_duplicates: later.warn_duplicates.or(self.warn_duplicates),
+            group_prefixes: later.group_prefixes.or(self.group_prefixes),
+            group_separator: later.group_separator.or(self.group_separator),
+            pin_marker_classes: later.pin_marker_classes.or(self.pin_marker_classes),
+        }
+    }
+}
+
+/// The `sortOrder` in the config file, either the full list of classes that replaces the default
+/// sort order, or classes to add before and after the default sort order
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub(crate) enum SortOrder {
+    Replace(Vec<String>),
+    Extend {
+        #[serde(default)]
+        prepend: Vec<String>,
+        #[serde(default)]
+        append: Vec<String>,
+    },
+}
+
+/// The config in a `package.json`, the other keys are ignored
+#[derive(Deserialize)]
+struct PackageJson {
+    rustywind: Option<ConfigFileContents>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct CustomRegexEntry {
+    pub(crate) container: String,
+    pub(crate) class: String,
 }
 
 #[derive(Debug)]
 pub struct Options {
     pub stdin: Option<String>,
+    /// Only the classes found between these byte offsets are sorted
+    pub range: Option<Range<usize>>,
+    /// The sorted stdin, or the single input file, is written to this file instead
+    pub output: Option<PathBuf>,
     pub write_mode: WriteMode,
+    /// With `WriteMode::DryRun`, the files that would be changed are printed as a json plan
+    pub json_plan: bool,
+    /// With `WriteMode::CheckFormatted`, whether the files are formatted and the files that are
+    /// not are printed as json
+    pub json_check: bool,
+    /// Exits with status 1 in dry run mode when any file would be changed
+    pub exit_code_on_change: bool,
+    /// Reports the class attributes that the finder couldn't match and exits with status 1
+    pub strict: bool,
     pub regex: FinderRegex,
+    pub file_regex: Vec<FileRegex>,
+    /// The classes of the `.json` files are sorted in the strings at this field instead of with
+    /// the finder, see `json::sort_json_field`
+    pub json_field: Option<String>,
     pub sorter: Sorter,
+    pub tailwind_version: TailwindVersion,
+    /// The tailwind `prefix` of the utilities, ex: `tw-` for `tw-px-2`, it's ignored when looking
+    /// up the classes in the sorter
+    pub prefix: Option<String>,
     pub starting_paths: Vec<PathBuf>,
     pub allow_duplicates: bool,
+    /// Lowercases the classes before sorting them, except for their arbitrary values, so `Flex`
+    /// is sorted and deduplicated as `flex`
+    pub lowercase_classes: bool,
+    pub preserve_whitespace: bool,
+    /// Wraps the sorted classes onto multiple lines when they go past this many columns
+    pub max_line_length: Option<usize>,
+    /// The width of a tab when measuring the lines of `max_line_length`
+    pub tab_width: usize,
+    /// Indents the wrapped lines with tabs, otherwise the indentation of the line is reused
+    pub use_tabs: bool,
+    /// Prints a warning for every class string that had duplicated classes removed
+    pub warn_duplicates: bool,
+    /// Reports every class string that had duplicated classes removed as an error
+    pub fail_on_duplicate: bool,
+    pub custom_class_order: CustomClassOrder,
+    pub custom_class_position: CustomClassPosition,
+    /// Keeps the custom classes at their original position, this wins over `custom_class_order`
+    /// and `custom_class_position`
+    pub preserve_custom_position: bool,
+    /// These classes are not sorted, they are placed at the `ignored_class_position` in the order
+    /// they were written, ex: JS hooks like `js-toggle`
+    pub ignored_classes: Vec<String>,
+    pub ignored_class_position: IgnoredClassPosition,
+    pub class_separator: ClassSeparator,
+    /// Joins the sorted classes, a single space by default, see `get_join_separator_from_cli`. A
+    /// comma only joins the comma separated lists, the other lists are joined with a single space
+    pub join_separator: String,
+    /// The variants listed here are sorted first, in this order, the other variants keep the
+    /// order of `VARIANTS`
+    pub variant_order: Vec<String>,
+    /// A sorted class starting with one of these prefixes starts a new group, the groups are
+    /// separated by `group_separator` instead of a single space, ex: `["bg-", "text-"]`
+    pub group_prefixes: Vec<String>,
+    /// Only whitespace, so the groups are split into classes again on the next run
+    pub group_separator: String,
+    /// The `MARKER_CLASSES`, ex: `group` and `peer`, are placed before the other classes in their
+    /// original order, instead of where the sorter puts them
+    pub pin_marker_classes: bool,
+    pub backup: bool,
+    pub restage: bool,
+    /// Asks before writing each changed file, only with `WriteMode::ToFile`
+    pub interactive: bool,
+    /// With `WriteMode::Diff`, only the changed class strings are printed instead of a unified diff
+    pub only_changed: bool,
+    pub color: bool,
+    pub stats: bool,
+    /// Only the errors and the output of the stdin, json and diff modes are printed
+    pub quiet: bool,
+    /// The files that were already sorted in a previous run, they are not sorted again. The files
+    /// that are sorted in this run are recorded, it's only saved with `WriteMode::ToFile`
+    pub cache: Option<Cache>,
     pub search_paths: Vec<PathBuf>,
-    pub ignored_files: HashSet<PathBuf>,
+    pub extension_filter: ExtensionFilter,
+    /// Keeps running and sorts the files again when they change
+    pub watch: bool,
+    pub walk_options: WalkOptions,
+    /// Matched against the canonicalized path of the files, see `parse_ignored_files`
+    pub ignored_files: GlobSet,
+    /// The globs of `ignored_files`, only used to print them
+    pub ignored_globs: Vec<String>,
+    /// The files can only be written inside of these canonicalized directories, when it's not empty
+    pub fix_only: Vec<PathBuf>,
+    /// The config files that were read, in the order they were merged
+    pub config_files: Vec<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            stdin: None,
+            range: None,
+            output: None,
+            write_mode: WriteMode::DryRun,
+            json_plan: false,
+            json_check: false,
+            exit_code_on_change: false,
+            strict: false,
+            regex: FinderRegex::DefaultRegex,
+            file_regex: vec![],
+            json_field: None,
+            sorter: Sorter::DefaultSorter,
+            tailwind_version: TailwindVersion::V3,
+            prefix: None,
+            starting_paths: vec![],
+            allow_duplicates: false,
+            lowercase_classes: false,
+            preserve_whitespace: false,
+            max_line_length: None,
+            tab_width: DEFAULT_TAB_WIDTH,
+            use_tabs: false,
+            warn_duplicates: false,
+            fail_on_duplicate: false,
+            custom_class_order: CustomClassOrder::Original,
+            custom_class_position: CustomClassPosition::Last,
+            preserve_custom_position: false,
+            ignored_classes: vec![],
+            ignored_class_position: IgnoredClassPosition::First,
+            class_separator: ClassSeparator::Whitespace,
+            join_separator: " ".to_string(),
+            variant_order: vec![],
+            group_prefixes: vec![],
+            group_separator: DEFAULT_GROUP_SEPARATOR.to_string(),
+            pin_marker_classes: false,
+            backup: false,
+            restage: false,
+            interactive: false,
+            only_changed: false,
+            color: false,
+            stats: false,
+            quiet: false,
+            cache: None,
+            search_paths: vec![],
+            extension_filter: ExtensionFilter::default(),
+            watch: false,
+            walk_options: WalkOptions::default(),
+            ignored_files: GlobSet::empty(),
+            ignored_globs: vec![],
+            fix_only: vec![],
+            config_files: vec![],
+        }
+    }
+}
+
+/// Builds `Options` for library usage without going through the CLI
+///
+/// ```
+/// use rustywind::{sort_classes_in_str, Options};
+///
+/// let options = Options::builder().allow_duplicates(true).build();
+/// assert_eq!(sort_classes_in_str(r#"<a class="px-2 flex">"#, &options), r#"<a class="flex px-2">"#);
+/// ```
+#[derive(Debug, Default)]
+pub struct OptionsBuilder {
+    options: Options,
+}
+
+impl OptionsBuilder {
+    pub fn regex(mut self, regex: FinderRegex) -> Self {
+        self.options.regex = regex;
+        self
+    }
+
+    /// Uses a custom regex to find the classes, it requires at-least 2 capture groups
+    pub fn custom_regex(self, regex: &str) -> Result<Self> {
+        Ok(self.regex(FinderRegex::CustomRegex(parse_regex_string(regex)?)))
+    }
+
+    /// Uses the finder instead of the global one for the files matching the glob, ex: `*.jsx`
+    pub fn file_regex(mut self, glob: &str, regex: FinderRegex) -> Result<Self> {
+        self.options.file_regex.push(FileRegex {
+            glob: parse_glob(glob)?,
+            regex,
+        });
+
+        Ok(self)
+    }
+
+    pub fn json_field(mut self, json_field: &str) -> Self {
+        self.options.json_field = Some(json_field.to_string());
+        self
+    }
+
+    pub fn sorter(mut self, sorter: Sorter) -> Self {
+        self.options.sorter = sorter;
+        self
+    }
+
+    /// Uses a custom sorter, with the classes sorted in the order given
+    pub fn sort_order(self, sort_order: Vec<String>) -> Self {
+        self.sorter(Sorter::CustomSorter(parse_custom_sorter(sort_order)))
+    }
+
+    /// The version of the default sort order, only used when no custom sorter is set
+    pub fn tailwind_version(mut self, tailwind_version: TailwindVersion) -> Self {
+        self.options.tailwind_version = tailwind_version;
+        self
+    }
+
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.options.prefix = Some(prefix.to_string());
+        self
+    }
+
+    pub fn allow_duplicates(mut self, allow_duplicates: bool) -> Self {
+        self.options.allow_duplicates = allow_duplicates;
+        self
+    }
+
+    pub fn lowercase_classes(mut self, lowercase_classes: bool) -> Self {
+        self.options.lowercase_classes = lowercase_classes;
+        self
+    }
+
+    /// Keeps the whitespace between the classes instead of joining them with a single space
+    pub fn preserve_whitespace(mut self, preserve_whitespace: bool) -> Self {
+        self.options.preserve_whitespace = preserve_whitespace;
+        self
+    }
+
+    /// Wraps the sorted classes onto multiple lines when they go past this many columns
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        self.options.max_line_length = Some(max_line_length);
+        self
+    }
+
+    /// The width of a tab when measuring the lines of `max_line_length`, defaults to 4
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.options.tab_width = tab_width;
+        self
+    }
+
+    /// Indents the lines wrapped by `max_line_length` with tabs
+    pub fn use_tabs(mut self, use_tabs: bool) -> Self {
+        self.options.use_tabs = use_tabs;
+        self
+    }
+
+    pub fn custom_class_order(mut self, custom_class_order: CustomClassOrder) -> Self {
+        self.options.custom_class_order = custom_class_order;
+        self
+    }
+
+    pub fn custom_class_position(mut self, custom_class_position: CustomClassPosition) -> Self {
+        self.options.custom_class_position = custom_class_position;
+        self
+    }
+
+    /// Keeps the custom classes where they were written, only the tailwind classes are reordered
+    pub fn preserve_custom_position(mut self, preserve_custom_position: bool) -> Self {
+        self.options.preserve_custom_position = preserve_custom_position;
+        self
+    }
+
+    /// These classes are not sorted, they are placed at the `ignored_class_position`
+    pub fn ignored_classes(mut self, ignored_classes: Vec<String>) -> Self {
+        self.options.ignored_classes = ignored_classes;
+        self
+    }
+
+    pub fn ignored_class_position(mut self, ignored_class_position: IgnoredClassPosition) -> Self {
+        self.options.ignored_class_position = ignored_class_position;
+        self
+    }
+
+    /// With `ClassSeparator::Comma` the classes can also be separated by commas, the original
+    /// separators are kept
+    pub fn class_separator(mut self, class_separator: ClassSeparator) -> Self {
+        self.options.class_separator = class_separator;
+        self
+    }
+
+    /// Joins the sorted classes with this instead of a single space, it has to be split again by
+    /// the `class_separator`, ex: `", "` with `ClassSeparator::Comma`. A comma only joins the lists
+    /// that were already separated by commas
+    pub fn join_separator(mut self, join_separator: &str) -> Self {
+        self.options.join_separator = join_separator.to_string();
+        self
+    }
+
+    /// Only sorts the classes found between these byte offsets
+    pub fn range(mut self, range: Range<usize>) -> Self {
+        self.options.range = Some(range);
+        self
+    }
+
+    /// Sorts the variants in this order, the variants not listed are sorted after them
+    pub fn variant_order(mut self, variant_order: Vec<String>) -> Self {
+        self.options.variant_order = variant_order;
+        self
+    }
+
+    /// Separates the groups of sorted classes that start with one of these prefixes
+    pub fn group_prefixes(mut self, group_prefixes: Vec<String>) -> Self {
+        self.options.group_prefixes = group_prefixes;
+        self
+    }
+
+    /// Places the marker classes, ex: `group` and `peer`, before the other classes
+    pub fn pin_marker_classes(mut self, pin_marker_classes: bool) -> Self {
+        self.options.pin_marker_classes = pin_marker_classes;
+        self
+    }
+
+    pub fn write_mode(mut self, write_mode: WriteMode) -> Self {
+        self.options.write_mode = write_mode;
+        self
+    }
+
+    pub fn build(self) -> Options {
+        self.options
+    }
 }
 
 impl Options {
+    pub fn builder() -> OptionsBuilder {
+        OptionsBuilder::default()
+    }
+
+    /// Returns the finder to use for the file, the first `file_regex` matching the path wins,
+    /// otherwise `.vue` files also sort the `:class` bindings, `.tsx`/`.jsx` files the `className`
+    /// template literals, `.heex`/`.eex` files the `class={...}` bindings and `.css`/`.scss` files
+    /// sort the `@apply` directives, unless a custom finder was set. The finders are compiled once,
+    /// when the options are built or on first use for the extension finders, and only looked up
+    /// here, so no regex is compiled while walking the files
+    pub fn regex_for_path(&self, path: &Path) -> &FinderRegex {
+        if let Some(file_regex) = self
+            .file_regex
+            .iter()
+            .find(|file_regex| file_regex.glob.is_match(path))
+        {
+            tracing::trace!(
+                glob = file_regex.glob.glob().glob(),
+                "using the fileRegex finders"
+            );
+            return &file_regex.regex;
+        }
+
+        let (finder, regex) = match (&self.regex, path.extension().and_then(OsStr::to_str)) {
+            (FinderRegex::DefaultRegex, Some("vue")) => ("vue", &*presets::VUE_FINDER),
+            (FinderRegex::DefaultRegex, Some("tsx" | "jsx")) => ("tsx", &*presets::TSX_FINDER),
+            (FinderRegex::DefaultRegex, Some("heex" | "eex")) => ("heex", &*presets::HEEX_FINDER),
+            (FinderRegex::DefaultRegex, Some("css" | "scss")) => ("css", &*presets::CSS_FINDER),
+            (regex, _) => ("global", regex),
+        };
+
+        tracing::trace!(finder, "using the finder for the extension");
+        regex
+    }
+
+    /// The classes of the sorter in use, in the order they are sorted, including the changes
+    /// made by the `sortOrder` of the config file
+    pub fn sort_order(&self) -> Vec<&str> {
+        let sorter = match &self.sorter {
+            Sorter::DefaultSorter => self.tailwind_version.default_sorter(),
+            Sorter::CustomSorter(custom_sorter) => custom_sorter,
+        };
+
+        sorter
+            .iter()
+            .sorted_by_key(|(_class, index)| **index)
+            .map(|(class, _index)| class.as_str())
+            .collect()
+    }
+
+    /// Reads and sorts the file, without writing anything. Errors if the file can't be read
+    pub fn apply(&self, path: &Path) -> Result<Outcome> {
+        let _span = tracing::debug_span!("file", path = %path.display()).entered();
+
+        if self.is_ignored(path) {
+            tracing::debug!("found in ignored_files, will not sort");
+            return Ok(Outcome::Skipped);
+        }
+
+        if self.is_too_large(path) {
+            tracing::info!("larger than --max-filesize, will not sort");
+            return Ok(Outcome::Skipped);
+        }
+
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read the file {}", path.display()))?;
+
+        if let Some(cache) = &self.cache {
+            if cache.is_sorted(path, &contents) {
+                tracing::debug!("already sorted according to the cache, will not sort");
+                return Ok(Outcome::Unchanged { contents });
+            }
+        }
+
+        if !self.has_classes_in_contents(path, &contents) {
+            tracing::trace!("no classes found, will not sort");
+            return Ok(Outcome::Skipped);
+        }
+
+        let (sorted_contents, changes) = self.sort_contents(path, &contents);
+        tracing::trace!(changes = changes.len(), "sorted");
+
+        match sorted_contents {
+            Cow::Borrowed(_) => {
+                if let Some(cache) = &self.cache {
+                    cache.record(path, &contents);
+                }
+
+                Ok(Outcome::Unchanged { contents })
+            }
+            Cow::Owned(sorted_contents) => Ok(Outcome::Changed {
+                before: contents,
+                after: sorted_contents,
+                changes,
+            }),
+        }
+    }
+
+    /// Whether the finder for the file matches any class, without sorting it. An ignored file has
+    /// no classes, errors if the file can't be read
+    pub fn has_classes(&self, path: &Path) -> Result<bool> {
+        if self.is_ignored(path) || self.is_too_large(path) {
+            return Ok(false);
+        }
+
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read the file {}", path.display()))?;
+
+        Ok(self.has_classes_in_contents(path, &contents))
+    }
+
+    /// The classes of the file that are not in the sorter, for `--dump-unknown-classes`
+    pub fn unknown_classes(&self, path: &Path) -> Result<Vec<String>> {
+        if self.is_ignored(path) || self.is_too_large(path) {
+            return Ok(vec![]);
+        }
+
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read the file {}", path.display()))?;
+
+        match self.json_field_for_path(path) {
+            Some(json_field) => Ok(json::find_unknown_classes(&contents, json_field, self)),
+            None => Ok(utils::find_unknown_classes(
+                &contents,
+                self.regex_for_path(path),
+                self,
+            )),
+        }
+    }
+
+    /// The `json_field` to sort in the file, only `.json` files are sorted with it
+    pub fn json_field_for_path(&self, path: &Path) -> Option<&str> {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("json") => self.json_field.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Sorts the contents of the file with its `json_field`, or with its finder
+    pub fn sort_contents<'a>(
+        &self,
+        path: &Path,
+        contents: &'a str,
+    ) -> (Cow<'a, str>, Vec<ClassChange>) {
+        match self.json_field_for_path(path) {
+            Some(json_field) => json::sort_json_field(contents, json_field, self),
+            None => utils::sort_file_contents_with_regex(contents, self.regex_for_path(path), self),
+        }
+    }
+
+    fn has_classes_in_contents(&self, path: &Path, contents: &str) -> bool {
+        match self.json_field_for_path(path) {
+            Some(json_field) => json::has_json_field(contents, json_field),
+            None => utils::has_classes_with_regex(contents, self.regex_for_path(path)),
+        }
+    }
+
+    /// Whether the file matches one of the `ignored_files`
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        if self.ignored_files.is_empty() {
+            // if the ignored_files is empty no need to do any more work
+            false
+        } else {
+            path.canonicalize()
+                .map(|path| self.ignored_files.is_match(path))
+                .unwrap_or(false)
+        }
+    }
+
+    /// Whether the file is larger than `max_filesize`, the files passed with `--files-from`,
+    /// `--staged` or `--since` are not walked so they are also checked before being read
+    pub fn is_too_large(&self, path: &Path) -> bool {
+        match self.walk_options.max_filesize {
+            Some(max_filesize) => fs::metadata(path)
+                .map(|metadata| metadata.len() > max_filesize)
+                .unwrap_or(false),
+            None => false,
+        }
+    }
+
+    /// Errors if the file is outside of the `fix_only` directories, the symlinks are resolved first.
+    /// A file that doesn't exist yet is resolved from its parent directory
+    pub fn check_fix_only(&self, path: &Path) -> Result<()> {
+        if self.fix_only.is_empty() {
+            return Ok(());
+        }
+
+        let resolved_path = match (path.canonicalize(), path.parent(), path.file_name()) {
+            (Ok(resolved_path), _, _) => Some(resolved_path),
+            (Err(_), Some(parent), Some(file_name)) => {
+                let parent = if parent.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    parent
+                };
+
+                parent
+                    .canonicalize()
+                    .ok()
+                    .map(|parent| parent.join(file_name))
+            }
+            _ => None,
+        };
+
+        match resolved_path {
+            Some(resolved_path)
+                if self
+                    .fix_only
+                    .iter()
+                    .any(|dir| resolved_path.starts_with(dir)) =>
+            {
+                Ok(())
+            }
+            _ => Err(eyre::eyre!(
+                "Refusing to write {}, it is outside of the --fix-only directories",
+                path.display()
+            ))
+            .with_suggestion(|| "Pass the directory of the file with --fix-only to allow it"),
+        }
+    }
+
+    /// Writes the sorted contents, a file that is already sorted is not written. With `backup`, the
+    /// original contents are saved to `<file>.bak` first, replacing an older backup, and the file
+    /// is not written when the backup can't be saved. The written file is recorded in the `cache`,
+    /// so it's skipped on the next run
+    pub fn write_sorted_file(
+        &self,
+        path: &Path,
+        sorted_contents: &str,
+        original_contents: &str,
+    ) -> Result<()> {
+        if sorted_contents == original_contents {
+            return Ok(());
+        }
+
+        if self.backup {
+            let backup_path = get_backup_file_path(path);
+
+            fs::write(&backup_path, original_contents.as_bytes()).wrap_err_with(|| {
+                format!(
+                    "Unable to save the backup file {}, {} was not changed",
+                    backup_path.display(),
+                    path.display()
+                )
+            })?;
+        }
+
+        fs::write(path, sorted_contents.as_bytes())
+            .wrap_err_with(|| format!("Unable to save the file {}", path.display()))?;
+
+        if let Some(cache) = &self.cache {
+            cache.record(path, sorted_contents);
+        }
+
+        Ok(())
+    }
+
+    /// Whether the file would be walked from its directory, used to check a single file without
+    /// walking the whole tree. The ignore files of the parent directories are respected
+    pub fn is_search_path(&self, path: &Path) -> bool {
+        let dir = match path.parent() {
+            Some(dir) if path.is_file() && self.extension_filter.is_match(path) => dir,
+            _ => return false,
+        };
+
+        get_walk_builder(&[dir.to_owned()], &self.walk_options)
+            .max_depth(Some(1))
+            .build()
+            .filter_map(Result::ok)
+            .any(|file| file.path() == path)
+    }
+
     pub fn new_from_cli(cli: Cli) -> Result<Options> {
+        validate_stdin_from_cli(&cli)?;
+        validate_config_file_from_cli(&cli)?;
+
         let stdin = if cli.stdin {
             let mut buffer = String::new();
             let mut stdin = std::io::stdin(); // We get `Stdin` here.
@@ -63,72 +875,873 @@ impl Options {
             None
         };
 
-        let starting_paths = get_starting_path_from_cli(&cli);
-        let search_paths = get_search_paths_from_starting_paths(&starting_paths);
+        let starting_paths = get_starting_path_from_cli(&cli)?;
+        let extension_filter = ExtensionFilter::from_cli(&cli);
+        let walk_options = WalkOptions::from_cli(&cli);
 
-        Ok(Options {
+        // the files passed with --files-from, --files, --staged or --since are used as is, without
+        // walking any directory, the deleted files are skipped
+        let search_paths = if cli.files_from.is_some()
+            || !cli.files.is_empty()
+            || cli.staged
+            || cli.since.is_some()
+        {
+            get_search_paths_from_file_list(&starting_paths, &extension_filter)
+        } else {
+            get_search_paths_from_starting_paths(
+                &starting_paths,
+                &extension_filter,
+                cli.threads,
+                &walk_options,
+            )
+        };
+        let config_files = get_config_files_from_cli(&cli, &starting_paths);
+        let mut config = get_options_from_config(&config_files)?;
+        if let Some(sort_order) = get_sort_order_file_from_cli(&cli)? {
+            config.sort_order = Some(SortOrder::Replace(sort_order));
+        }
+        let write_mode = get_write_mode_from_cli(&cli);
+        let group_separator = get_group_separator_from_config(&config)?;
+        let class_separator = cli
+            .class_separator
+            .or(config.class_separator)
+            .unwrap_or_default();
+        let join_separator = get_join_separator_from_cli(&cli, class_separator)?;
+        let ignored_globs = get_ignored_globs_from_cli(&cli)?;
+
+        let mut options = Options {
             stdin,
+            range: get_range_from_cli(&cli)?,
+            output: get_output_from_cli(&cli, &starting_paths)?,
             starting_paths,
             search_paths,
-            write_mode: get_write_mode_from_cli(&cli),
-            regex: get_custom_regex_from_cli(&cli)?,
-            sorter: get_sorter_from_cli(&cli)?,
-            allow_duplicates: cli.allow_duplicates,
-            ignored_files: get_ignored_files_from_cli(&cli),
-        })
+            write_mode,
+            json_plan: cli.dry_run && cli.output_format == OutputFormat::Json,
+            json_check: cli.check_formatted && cli.output_format == OutputFormat::Json,
+            exit_code_on_change: cli.exit_code_on_change,
+            strict: cli.strict,
+            regex: get_custom_regex_from_cli(&cli, &config)?,
+            file_regex: get_file_regex_from_config(&config)?,
+            json_field: cli.json_field.clone(),
+            sorter: get_sorter_from_config(
+                config.sort_order,
+                config.extend_default.unwrap_or(false),
+                cli.tailwind_version,
+            ),
+            tailwind_version: cli.tailwind_version,
+            prefix: cli.prefix.clone(),
+            allow_duplicates: get_allow_duplicates_from_cli(&cli),
+            lowercase_classes: cli.lowercase_classes,
+            preserve_whitespace: cli.preserve_whitespace,
+            max_line_length: cli.max_line_length,
+            tab_width: get_tab_width_from_cli(&cli)?,
+            use_tabs: cli.use_tabs,
+            warn_duplicates: cli.warn_duplicates || config.warn_duplicates.unwrap_or_default(),
+            fail_on_duplicate: cli.fail_on_duplicate,
+            custom_class_order: cli.sort_custom.or(config.sort_custom).unwrap_or_default(),
+            custom_class_position: cli
+                .custom_class_position
+                .or(config.custom_class_position)
+                .unwrap_or_default(),
+            preserve_custom_position: cli.preserve_custom_position
+                || config.preserve_custom_position.unwrap_or_default(),
+            ignored_classes: cli
+                .ignore_class
+                .iter()
+                .cloned()
+                .chain(config.ignore_classes.unwrap_or_default())
+                .collect(),
+            ignored_class_position: cli
+                .ignored_class_position
+                .or(config.ignored_class_position)
+                .unwrap_or_default(),
+            class_separator,
+            join_separator,
+            variant_order: config.variant_order.unwrap_or_default(),
+            group_prefixes: config.group_prefixes.unwrap_or_default(),
+            group_separator,
+            pin_marker_classes: config.pin_marker_classes.unwrap_or_default(),
+            backup: cli.backup,
+            restage: cli.restage,
+            interactive: cli.interactive,
+            only_changed: cli.only_changed,
+            color: cli.color,
+            stats: cli.stats,
+            quiet: cli.quiet,
+            extension_filter,
+            watch: cli.watch,
+            walk_options,
+            cache: None,
+            ignored_files: build_glob_set(&ignored_globs)?,
+            ignored_globs: ignored_globs
+                .iter()
+                .map(|glob| glob.glob().to_string())
+                .collect(),
+            fix_only: get_fix_only_from_cli(&cli)?,
+            config_files,
+        };
+
+        // the cache key is a hash of the resolved options, so they are resolved first
+        if !cli.no_cache {
+            options.cache = load_cache(&options);
+        }
+
+        Ok(options)
     }
 }
 
-fn get_sorter_from_cli(cli: &Cli) -> Result<Sorter> {
-    match &cli.config_file {
-        Some(config_file) => {
-            let file_contents = fs::read_to_string(config_file)
-                .wrap_err_with(|| format!("Error reading the config file {config_file}"))
-                .with_suggestion(|| format!("Make sure the file {config_file} exists"));
+pub(crate) fn get_backup_file_path(path: &Path) -> PathBuf {
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(".bak");
+    PathBuf::from(backup_path)
+}
+
+fn get_tab_width_from_cli(cli: &Cli) -> Result<usize> {
+    match cli.tab_width {
+        Some(0) => Err(eyre::eyre!("--tab-width has to be at least 1"))
+            .with_suggestion(|| "Pass the number of columns of a tab, ex: --tab-width 4"),
+        Some(tab_width) => Ok(tab_width),
+        None => Ok(DEFAULT_TAB_WIDTH),
+    }
+}
+
+/// `--no-dedup` always keeps the duplicated classes, the same as `--allow-duplicates`
+pub(crate) fn get_allow_duplicates_from_cli(cli: &Cli) -> bool {
+    cli.allow_duplicates || cli.no_dedup
+}
+
+/// The sorted classes are split again on the next run, so the `--join-separator` has to be
+/// whitespace, or a comma with whitespace around it when the classes are separated by commas
+pub(crate) fn get_join_separator_from_cli(
+    cli: &Cli,
+    class_separator: ClassSeparator,
+) -> Result<String> {
+    let separator = match &cli.join_separator {
+        Some(separator) => separator,
+        None => return Ok(" ".to_string()),
+    };
 
-            let config_file: ConfigFileContents = serde_json::from_str(&file_contents?)
-                .wrap_err_with(|| format!("Error while parsing the config file {config_file}"))
-                .with_suggestion(|| {
-                    format!("Make sure the {config_file} is valid json, with the expected format")
-                })?;
+    let is_whitespace = !separator.is_empty() && separator.chars().all(char::is_whitespace);
+    let is_comma = separator.trim() == ",";
 
-            Ok(Sorter::CustomSorter(parse_custom_sorter(
-                config_file.sort_order,
-            )))
+    match class_separator {
+        _ if is_whitespace => Ok(separator.clone()),
+        ClassSeparator::Comma if is_comma => Ok(separator.clone()),
+        ClassSeparator::Comma => Err(eyre::eyre!(
+            "The --join-separator {separator:?} is not whitespace or a comma"
+        ))
+        .with_suggestion(|| "Use spaces, line breaks or a comma, ex: \", \""),
+        ClassSeparator::Whitespace => Err(eyre::eyre!(
+            "The --join-separator {separator:?} is not whitespace, the classes could not be split \
+            again"
+        ))
+        .with_suggestion(|| {
+            "Use spaces or line breaks, or add --class-separator comma to join them with a comma"
+        }),
+    }
+}
+
+/// The `groupSeparator` has to be whitespace, any other character would be sorted as a class
+fn get_group_separator_from_config(config: &ConfigFileContents) -> Result<String> {
+    match &config.group_separator {
+        Some(separator) if separator.is_empty() || !separator.chars().all(char::is_whitespace) => {
+            Err(eyre::eyre!(
+                "The groupSeparator {separator:?} of the config file is not whitespace"
+            ))
+            .with_suggestion(|| "Use spaces or line breaks, ex: \"  \"")
         }
-        None => Ok(Sorter::DefaultSorter),
+        Some(separator) => Ok(separator.clone()),
+        None => Ok(DEFAULT_GROUP_SEPARATOR.to_string()),
     }
 }
 
-fn get_custom_regex_from_cli(cli: &Cli) -> Result<FinderRegex> {
-    match &cli.custom_regex {
-        Some(regex_string) => {
-            let regex = Regex::new(regex_string).wrap_err("Unable to parse custom regex")?;
+/// The config files passed with `--config-file`, otherwise the config file found from the first
+/// starting path
+fn get_config_files_from_cli(cli: &Cli, starting_paths: &[PathBuf]) -> Vec<PathBuf> {
+    if !cli.config_file.is_empty() {
+        return cli.config_file.iter().map(PathBuf::from).collect();
+    }
+
+    if cli.no_auto_config {
+        return vec![];
+    }
+
+    let current_dir = PathBuf::from(".");
+    let start = starting_paths.first().unwrap_or(&current_dir);
 
-            if regex.captures_len() < 2 {
-                eyre::bail!("custom regex error, requires at-least 2 capture groups");
-            }
+    find_config_file(start).into_iter().collect()
+}
+
+/// Reads the config files and merges them in order, see `ConfigFileContents::merge`
+fn get_options_from_config(config_files: &[PathBuf]) -> Result<ConfigFileContents> {
+    config_files
+        .iter()
+        .try_fold(ConfigFileContents::default(), |config, config_file| {
+            Ok(config.merge(read_config_file(&config_file.display().to_string())?))
+        })
+}
+
+/// The classes of `--sort-order-file`, one per line, the empty lines are skipped
+fn get_sort_order_file_from_cli(cli: &Cli) -> Result<Option<Vec<String>>> {
+    let sort_order_file = match &cli.sort_order_file {
+        Some(sort_order_file) => sort_order_file,
+        None => return Ok(None),
+    };
+
+    let file_contents = fs::read_to_string(sort_order_file)
+        .wrap_err_with(|| format!("Error reading the sort order file {sort_order_file}"))
+        .with_suggestion(|| format!("Make sure the file {sort_order_file} exists"))?;
+
+    Ok(Some(parse_sort_order_file(&file_contents)))
+}
+
+pub(crate) fn parse_sort_order_file(file_contents: &str) -> Vec<String> {
+    utils::strip_bom(file_contents)
+        .lines()
+        .map(str::trim)
+        .filter(|class| !class.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `-` reads the config json from stdin
+fn read_config_file(config_file: &str) -> Result<ConfigFileContents> {
+    if config_file == "-" {
+        let mut file_contents = String::new();
+        std::io::stdin()
+            .read_to_string(&mut file_contents)
+            .wrap_err("Error reading the config from stdin")?;
+
+        return parse_config_file("stdin", utils::strip_bom(&file_contents));
+    }
+
+    let file_contents = fs::read_to_string(config_file)
+        .wrap_err_with(|| format!("Error reading the config file {config_file}"))
+        .with_suggestion(|| format!("Make sure the file {config_file} exists"));
+
+    parse_config_file(config_file, utils::strip_bom(&file_contents?))
+}
+
+/// Looks for one of the `CONFIG_FILE_NAMES`, or a `package.json` with a `rustywind` key, in the
+/// directory of the path and then in each of its parents, until the filesystem root
+pub(crate) fn find_config_file(path: &Path) -> Option<PathBuf> {
+    let path = path.canonicalize().ok()?;
+    let dir = if path.is_file() {
+        path.parent()?
+    } else {
+        &path
+    };
+
+    let config_file = dir
+        .ancestors()
+        .flat_map(|dir| {
+            CONFIG_FILE_NAMES
+                .iter()
+                .chain(&[PACKAGE_JSON])
+                .map(move |name| dir.join(name))
+        })
+        .find(|config_file| {
+            config_file.is_file()
+                && (!is_package_json(config_file) || has_package_json_config(config_file))
+        });
 
-            Ok(FinderRegex::CustomRegex(regex))
+    if let Some(config_file) = &config_file {
+        tracing::debug!("using the config file found at {config_file:#?}");
+    }
+
+    config_file
+}
+
+fn is_package_json(path: &Path) -> bool {
+    path.file_name() == Some(OsStr::new(PACKAGE_JSON))
+}
+
+/// Whether the `package.json` has a `rustywind` key, an invalid config under the key is still used,
+/// so its error is reported
+fn has_package_json_config(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| {
+            serde_json::from_str::<serde_json::Value>(utils::strip_bom(&contents)).ok()
+        })
+        .is_some_and(|package_json| package_json.get("rustywind").is_some())
+}
+
+/// Parses the config file as toml if it has a `.toml` extension, otherwise as json. The config
+/// of a `package.json` is read from its `rustywind` key
+pub(crate) fn parse_config_file(
+    config_file: &str,
+    file_contents: &str,
+) -> Result<ConfigFileContents> {
+    if is_package_json(Path::new(config_file)) {
+        let package_json: PackageJson = serde_json::from_str(file_contents)
+            .wrap_err_with(|| format!("Error while parsing the config file {config_file}"))
+            .with_suggestion(|| {
+                format!("Make sure the rustywind key of {config_file} has the expected format")
+            })?;
+
+        return package_json
+            .rustywind
+            .ok_or_else(|| eyre::eyre!("{config_file} has no rustywind key"))
+            .with_suggestion(|| {
+                format!("Add the config under a \"rustywind\" key in {config_file}")
+            });
+    }
+
+    let is_toml = Path::new(config_file).extension().and_then(OsStr::to_str) == Some("toml");
+
+    let format = if is_toml { "toml" } else { "json" };
+    let error_message = || format!("Error while parsing the config file {config_file}");
+
+    let config = if is_toml {
+        toml::from_str(file_contents).wrap_err_with(error_message)
+    } else {
+        serde_json::from_str(file_contents).wrap_err_with(error_message)
+    };
+
+    config.with_suggestion(|| {
+        format!("Make sure the {config_file} is valid {format}, with the expected format")
+    })
+}
+
+/// With `extendDefault` the default classes missing from a `sortOrder` array are sorted after it,
+/// in their default order, instead of with the custom classes
+pub(crate) fn get_sorter_from_config(
+    sort_order: Option<SortOrder>,
+    extend_default: bool,
+    tailwind_version: TailwindVersion,
+) -> Sorter {
+    match sort_order {
+        Some(SortOrder::Replace(sort_order)) if extend_default => Sorter::CustomSorter(
+            parse_extended_sorter(sort_order, vec![], tailwind_version.default_sorter()),
+        ),
+        Some(SortOrder::Replace(sort_order)) => {
+            Sorter::CustomSorter(parse_custom_sorter(sort_order))
         }
-        None => Ok(FinderRegex::DefaultRegex),
+        Some(SortOrder::Extend { prepend, append }) => Sorter::CustomSorter(parse_extended_sorter(
+            prepend,
+            append,
+            tailwind_version.default_sorter(),
+        )),
+        None => Sorter::DefaultSorter,
     }
 }
 
-fn get_starting_path_from_cli(cli: &Cli) -> Vec<PathBuf> {
-    cli.file_or_dir
+fn get_custom_regex_from_cli(cli: &Cli, config: &ConfigFileContents) -> Result<FinderRegex> {
+    let regex = get_finder_from_cli(cli, config)?;
+
+    if !cli.ignore_case {
+        return Ok(regex);
+    }
+
+    tracing::debug!("the finders ignore case");
+
+    // the classes are still sorted case-sensitively, only the matching of the finders changes
+    match regex {
+        FinderRegex::DefaultRegex => Ok(FinderRegex::CustomRegex(RE_IGNORE_CASE.clone())),
+        FinderRegex::CustomRegex(regex) => Ok(FinderRegex::CustomRegex(ignore_case(&regex)?)),
+        FinderRegex::CustomRegexEntries(entries) => entries
+            .iter()
+            .map(|entry| {
+                Ok(RegexPair {
+                    container_regex: ignore_case(&entry.container_regex)?,
+                    class_regex: ignore_case(&entry.class_regex)?,
+                })
+            })
+            .collect::<Result<_>>()
+            .map(FinderRegex::CustomRegexEntries),
+    }
+}
+
+/// Compiles the regex again with the case-insensitive flag
+fn ignore_case(regex: &Regex) -> Result<Regex> {
+    Regex::new(&format!("(?i){}", regex.as_str()))
+        .wrap_err_with(|| format!("Unable to make the regex `{regex}` ignore case"))
+}
+
+fn get_finder_from_cli(cli: &Cli, config: &ConfigFileContents) -> Result<FinderRegex> {
+    if let Some(regex_string) = &cli.custom_regex {
+        return Ok(FinderRegex::CustomRegex(parse_regex_string(regex_string)?));
+    }
+
+    let mut entries = vec![];
+
+    if cli.clsx {
+        let class_functions = match &config.class_functions {
+            Some(class_functions) => class_functions.iter().map(String::as_str).collect(),
+            None => CLASS_FUNCTIONS.to_vec(),
+        };
+
+        entries.push(presets::get_clsx_regex_pair(&class_functions)?);
+    }
+
+    if cli.tw_merge {
+        entries.push(presets::get_tw_merge_regex_pair());
+    }
+
+    if cli.vue {
+        entries.push(presets::get_vue_regex_pair());
+    }
+
+    if cli.angular {
+        entries.push(presets::get_angular_regex_pair());
+    }
+
+    if cli.alpine {
+        entries.push(presets::get_alpine_regex_pair());
+    }
+
+    if cli.leptos {
+        entries.push(presets::get_leptos_regex_pair());
+    }
+
+    if cli.maud {
+        entries.push(presets::get_maud_regex_pair());
+    }
+
+    if cli.heex {
+        entries.push(presets::get_heex_regex_pair());
+    }
+
+    if cli.css {
+        entries.push(presets::get_css_regex_pair());
+    }
+
+    if cli.tsx {
+        entries.push(presets::get_tsx_regex_pair());
+        entries.push(presets::get_concatenation_regex_pair());
+    }
+
+    if cli.handlebars {
+        entries.push(presets::get_handlebars_regex_pair());
+    }
+
+    if cli.cva {
+        entries.push(presets::get_cva_regex_pair());
+    }
+
+    // the presets only add finders, regular class attributes should still be sorted, with --mdx
+    // they are sorted outside of the fenced code blocks
+    if cli.mdx {
+        entries.insert(0, presets::get_mdx_regex_pair());
+    } else if !entries.is_empty() {
+        entries.insert(0, presets::get_default_regex_pair());
+    }
+
+    if let Some(custom_regex) = &config.custom_regex {
+        entries.append(&mut parse_custom_regex(custom_regex)?);
+    }
+
+    if entries.is_empty() {
+        tracing::debug!("using the default finder");
+        Ok(FinderRegex::DefaultRegex)
+    } else {
+        tracing::debug!("using {} finders", entries.len());
+        Ok(FinderRegex::CustomRegexEntries(entries))
+    }
+}
+
+/// The `fileRegex` in the config file maps a glob to the finders used for the matching files,
+/// the globs are tried in alphabetical order
+pub(crate) fn get_file_regex_from_config(config: &ConfigFileContents) -> Result<Vec<FileRegex>> {
+    let file_regex = match &config.file_regex {
+        Some(file_regex) => file_regex,
+        None => return Ok(vec![]),
+    };
+
+    file_regex
+        .iter()
+        .map(|(glob, custom_regex)| {
+            Ok(FileRegex {
+                glob: parse_glob(glob)?,
+                regex: FinderRegex::CustomRegexEntries(parse_custom_regex(custom_regex)?),
+            })
+        })
+        .collect()
+}
+
+fn parse_glob(glob: &str) -> Result<GlobMatcher> {
+    let glob = Glob::new(glob)
+        .wrap_err_with(|| format!("Unable to parse the file glob {glob}"))
+        .with_suggestion(|| "Use a glob like *.jsx or src/**/*.{ts,tsx}")?;
+
+    Ok(glob.compile_matcher())
+}
+
+fn parse_regex_string(regex_string: &str) -> Result<Regex> {
+    count_finder_compilation();
+    let regex = Regex::new(regex_string).wrap_err("Unable to parse custom regex")?;
+
+    if regex.captures_len() < 2 {
+        eyre::bail!("custom regex error, requires at-least 2 capture groups");
+    }
+
+    Ok(regex)
+}
+
+/// Errors name the entry (starting at 1) and whether its container or class pattern failed
+pub(crate) fn parse_custom_regex(custom_regex: &[CustomRegexEntry]) -> Result<Vec<RegexPair>> {
+    count_finder_compilation();
+
+    custom_regex
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let entry_number = index + 1;
+
+            let container_regex = Regex::new(&entry.container)
+                .wrap_err_with(|| {
+                    format!(
+                        "Unable to parse the container regex `{}` of customRegex entry {entry_number}",
+                        entry.container
+                    )
+                })
+                .with_suggestion(|| "Make sure the regex is valid and its backslashes are escaped")?;
+
+            let class_regex = Regex::new(&entry.class)
+                .wrap_err_with(|| {
+                    format!(
+                        "Unable to parse the class regex `{}` of customRegex entry {entry_number}",
+                        entry.class
+                    )
+                })
+                .with_suggestion(|| "Make sure the regex is valid and its backslashes are escaped")?;
+
+            tracing::debug!(
+                "customRegex entry {entry_number}: container `{}`, class `{}`",
+                entry.container,
+                entry.class
+            );
+
+            if class_regex.captures_len() < 2 {
+                return Err(eyre::eyre!(
+                    "custom regex error, the class regex `{}` of customRegex entry {entry_number} \
+                    requires at-least 2 capture groups",
+                    entry.class
+                ))
+                .with_suggestion(|| "Wrap the classes in a capture group, ex: \"([^\"]*)\"");
+            }
+
+            Ok(RegexPair {
+                container_regex,
+                class_regex,
+            })
+        })
+        .collect()
+}
+
+fn get_starting_path_from_cli(cli: &Cli) -> Result<Vec<PathBuf>> {
+    if let Some(files_from) = &cli.files_from {
+        return get_files_from(files_from);
+    }
+
+    if !cli.files.is_empty() {
+        return get_files_matching(&cli.files, &WalkOptions::from_cli(cli));
+    }
+
+    if cli.staged {
+        return git::get_staged_files(Path::new("."));
+    }
+
+    if let Some(git_ref) = &cli.since {
+        return git::get_changed_files_since(Path::new("."), git_ref);
+    }
+
+    Ok(cli
+        .file_or_dir
         .iter()
         .map(|path| Path::new(path).to_owned())
+        .collect())
+}
+
+/// Reads the newline separated list of files for `--files-from`, `-` reads it from stdin
+fn get_files_from(files_from: &str) -> Result<Vec<PathBuf>> {
+    let file_list = if files_from == "-" {
+        let mut buffer = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buffer)
+            .wrap_err("Error reading the list of files from stdin")?;
+        buffer
+    } else {
+        fs::read_to_string(files_from)
+            .wrap_err_with(|| format!("Error reading the list of files {files_from}"))
+            .with_suggestion(|| format!("Make sure the file {files_from} exists"))?
+    };
+
+    Ok(parse_file_list(&file_list))
+}
+
+/// Expands the globs of `--files`, only the directory before the first glob character is walked,
+/// so `src/**/*.html` doesn't walk anything outside of `src`
+pub(crate) fn get_files_matching(
+    globs: &[String],
+    walk_options: &WalkOptions,
+) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for glob in globs {
+        let pattern = glob.trim_start_matches("./");
+        let matcher = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .wrap_err_with(|| format!("Unable to parse the --files glob {glob}"))
+            .with_suggestion(|| "Use a glob like src/**/*.html")?
+            .compile_matcher();
+
+        let base_dir: PathBuf = Path::new(pattern)
+            .components()
+            .take_while(|component| {
+                !component
+                    .as_os_str()
+                    .to_string_lossy()
+                    .contains(['*', '?', '[', '{'])
+            })
+            .collect();
+
+        let base_dir = if base_dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            base_dir.as_path()
+        };
+
+        let matching_files = get_walk_builder(&[base_dir.to_owned()], walk_options)
+            .build()
+            .filter_map(Result::ok)
+            .map(|file| {
+                let path = file.path();
+                path.strip_prefix(".").unwrap_or(path).to_owned()
+            })
+            .filter(|path| matcher.is_match(path));
+
+        let files_before = files.len();
+        files.extend(matching_files);
+
+        if files.len() == files_before {
+            tracing::warn!(glob, "no files match the --files glob");
+        }
+    }
+
+    Ok(files)
+}
+
+pub(crate) fn parse_file_list(file_list: &str) -> Vec<PathBuf> {
+    file_list
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
         .collect()
 }
 
-fn get_write_mode_from_cli(cli: &Cli) -> WriteMode {
+/// The cache is only used by the modes that don't need the files that are already sorted
+fn load_cache(options: &Options) -> Option<Cache> {
+    match options.write_mode {
+        WriteMode::ToFile | WriteMode::DryRun | WriteMode::CheckFormatted | WriteMode::Diff => {
+            Some(Cache::load(
+                Path::new(CACHE_FILE_NAME),
+                get_cache_key(options),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// A hash of every option that changes how the files are sorted, and of the version. The options
+/// are destructured without `..`, so a new option has to be added to the key or ignored here
+pub(crate) fn get_cache_key(options: &Options) -> u64 {
+    let Options {
+        stdin: _,
+        range,
+        output: _,
+        write_mode: _,
+        json_plan: _,
+        json_check: _,
+        exit_code_on_change: _,
+        strict: _,
+        regex,
+        file_regex,
+        json_field,
+        sorter,
+        tailwind_version,
+        prefix,
+        starting_paths: _,
+        allow_duplicates,
+        lowercase_classes,
+        preserve_whitespace,
+        max_line_length,
+        tab_width,
+        use_tabs,
+        warn_duplicates,
+        fail_on_duplicate,
+        custom_class_order,
+        custom_class_position,
+        preserve_custom_position,
+        ignored_classes,
+        ignored_class_position,
+        class_separator,
+        join_separator,
+        variant_order,
+        group_prefixes,
+        group_separator,
+        pin_marker_classes,
+        backup: _,
+        restage: _,
+        interactive: _,
+        only_changed: _,
+        color: _,
+        stats: _,
+        quiet: _,
+        cache: _,
+        search_paths: _,
+        extension_filter: _,
+        watch: _,
+        walk_options: _,
+        ignored_files: _,
+        ignored_globs: _,
+        fix_only: _,
+        config_files: _,
+    } = options;
+
+    // the order of a `HashMap` changes between runs
+    let sorter = match sorter {
+        Sorter::DefaultSorter => None,
+        Sorter::CustomSorter(sorter) => Some(
+            sorter
+                .iter()
+                .map(|(class, index)| (index, class))
+                .sorted()
+                .collect::<Vec<_>>(),
+        ),
+    };
+
+    cache::hash((
+        env!("CARGO_PKG_VERSION"),
+        format!(
+            "{range:?} {regex:?} {file_regex:?} {json_field:?} {sorter:?} {tailwind_version:?} \
+            {prefix:?} {allow_duplicates} {lowercase_classes} {preserve_whitespace} \
+            {max_line_length:?} {tab_width} {use_tabs} {warn_duplicates} {fail_on_duplicate} \
+            {custom_class_order:?} {custom_class_position:?} {preserve_custom_position} \
+            {ignored_classes:?} {ignored_class_position:?} {class_separator:?} \
+            {join_separator:?} {variant_order:?} {group_prefixes:?} {group_separator:?} \
+            {pin_marker_classes}"
+        ),
+    ))
+}
+
+fn get_range_from_cli(cli: &Cli) -> Result<Option<Range<usize>>> {
+    match cli.range.as_deref() {
+        Some(&[start, end]) if start <= end => Ok(Some(start..end)),
+        Some(&[start, end]) => Err(eyre::eyre!("Invalid range {start} {end}"))
+            .with_suggestion(|| "The START of the range can't be after its END"),
+        _ => Ok(None),
+    }
+}
+
+/// `--output` needs a single input, the whole stdin or a single file
+fn get_output_from_cli(cli: &Cli, starting_paths: &[PathBuf]) -> Result<Option<PathBuf>> {
+    match (&cli.output, starting_paths) {
+        (None, _) => Ok(None),
+        (Some(output), _) if cli.stdin => Ok(Some(PathBuf::from(output))),
+        (Some(output), [path]) if path.is_file() => Ok(Some(PathBuf::from(output))),
+        (Some(output), _) => Err(eyre::eyre!(
+            "Unable to write to {output}, there is more than a single input"
+        ))
+        .with_suggestion(|| "--output can only be used with --stdin or a single file"),
+    }
+}
+
+/// `--config-file -` reads the config from stdin, so nothing else can read stdin
+pub(crate) fn validate_config_file_from_cli(cli: &Cli) -> Result<()> {
+    let from_stdin = cli
+        .config_file
+        .iter()
+        .filter(|config_file| *config_file == "-");
+
+    match from_stdin.count() {
+        0 => return Ok(()),
+        1 => (),
+        _ => {
+            return Err(eyre::eyre!("--config-file - can only be passed once"))
+                .with_suggestion(|| "Merge the configs into the one passed on stdin");
+        }
+    }
+
+    if cli.stdin {
+        return Err(eyre::eyre!(
+            "--config-file - can't be used with --stdin, both would read stdin"
+        ))
+        .with_suggestion(|| {
+            "Pass the config as a file, or pass the file to sort instead of --stdin"
+        });
+    }
+
+    if cli.files_from.as_deref() == Some("-") {
+        return Err(eyre::eyre!(
+            "--config-file - can't be used with --files-from -, both would read stdin"
+        ))
+        .with_suggestion(|| "Pass the config or the list of files as a file");
+    }
+
+    if cli.interactive {
+        return Err(eyre::eyre!(
+            "--config-file - can't be used with --interactive, the answers are read from stdin"
+        ))
+        .with_suggestion(|| "Pass the config as a file to use --interactive");
+    }
+
+    Ok(())
+}
+
+/// `--stdin` only sorts its input and prints it, it can't also run on paths or write to files.
+/// Checked before reading stdin so the command doesn't wait for an input that would be ignored
+pub(crate) fn validate_stdin_from_cli(cli: &Cli) -> Result<()> {
+    if !cli.stdin {
+        return Ok(());
+    }
+
+    if !cli.file_or_dir.is_empty() {
+        return Err(eyre::eyre!(
+            "--stdin can't be used with a path, got: {}",
+            cli.file_or_dir.join(" ")
+        ))
+        .with_suggestion(|| {
+            "Remove the path to sort stdin, or remove --stdin to run on the path, \
+            ex: rustywind --write ."
+        });
+    }
+
+    if cli.write {
+        return Err(eyre::eyre!(
+            "--stdin can't be used with --write, there is no file to write to"
+        ))
+        .with_suggestion(|| {
+            "The sorted input is printed to stdout, use --output <FILE> to write it to a file"
+        });
+    }
+
+    if cli.check_formatted {
+        return Err(eyre::eyre!("--stdin can't be used with --check-formatted"))
+            .with_suggestion(|| {
+                "Pass the file instead of --stdin to check it, ex: rustywind --check-formatted index.html"
+            });
+    }
+
+    Ok(())
+}
+
+pub(crate) fn get_write_mode_from_cli(cli: &Cli) -> WriteMode {
+    // `--dry-run --output-format json` prints the dry run as json, see `Options::json_plan`, and
+    // `--check-formatted --output-format json` the check, see `Options::json_check`
     if cli.dry_run {
         WriteMode::DryRun
-    } else if cli.write {
-        WriteMode::ToFile
     } else if cli.check_formatted {
         WriteMode::CheckFormatted
+    } else if cli.output_format == OutputFormat::Json {
+        WriteMode::Json
+    } else if cli.write {
+        WriteMode::ToFile
+    } else if cli.diff {
+        WriteMode::Diff
     } else if cli.stdin {
         WriteMode::ToStdOut
     } else {
@@ -136,33 +1749,273 @@ fn get_write_mode_from_cli(cli: &Cli) -> WriteMode {
     }
 }
 
-fn get_search_paths_from_starting_paths(starting_paths: &[PathBuf]) -> Vec<PathBuf> {
-    starting_paths
+/// Which of the files skipped by default are walked
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WalkOptions {
+    /// Walks the files ignored by `.gitignore` and `.ignore` files
+    pub no_ignore: bool,
+    /// Walks the hidden files and directories, ex: `.storybook`
+    pub hidden: bool,
+    /// Walks into the symlinked directories, the symlink loops are skipped
+    pub follow_symlinks: bool,
+    /// The files larger than this many bytes are not walked and not read
+    pub max_filesize: Option<u64>,
+}
+
+impl WalkOptions {
+    fn from_cli(cli: &Cli) -> Self {
+        WalkOptions {
+            no_ignore: cli.no_ignore,
+            hidden: cli.hidden,
+            follow_symlinks: cli.follow_symlinks,
+            max_filesize: cli.max_filesize,
+        }
+    }
+}
+
+/// Limits the walked files by extension, an extension can have multiple parts, ex: `min.js`
+#[derive(Debug, Default)]
+pub struct ExtensionFilter {
+    /// When not empty, only the files with one of these extensions are walked
+    pub include: Vec<String>,
+    /// The files with one of these extensions are never walked
+    pub exclude: Vec<String>,
+}
+
+impl ExtensionFilter {
+    fn from_cli(cli: &Cli) -> Self {
+        let normalize = |extensions: &Option<Vec<String>>| -> Vec<String> {
+            extensions
+                .iter()
+                .flatten()
+                .map(|extension| extension.trim().trim_start_matches('.').to_string())
+                .filter(|extension| !extension.is_empty())
+                .collect()
+        };
+
+        ExtensionFilter {
+            include: normalize(&cli.include_ext),
+            exclude: normalize(&cli.exclude_ext),
+        }
+    }
+
+    pub fn is_match(&self, path: &Path) -> bool {
+        let file_name = match path.file_name().and_then(OsStr::to_str) {
+            Some(file_name) => file_name,
+            None => return self.include.is_empty(),
+        };
+
+        let has_extension = |extension: &String| {
+            file_name
+                .strip_suffix(extension.as_str())
+                .is_some_and(|name| name.ends_with('.'))
+        };
+
+        (self.include.is_empty() || self.include.iter().any(has_extension))
+            && !self.exclude.iter().any(has_extension)
+    }
+}
+
+/// Walks the starting paths, on a single thread unless `threads` is set to more than 1, or to 0 to
+/// use the available parallelism
+pub(crate) fn get_search_paths_from_starting_paths(
+    starting_paths: &[PathBuf],
+    extension_filter: &ExtensionFilter,
+    threads: Option<usize>,
+    walk_options: &WalkOptions,
+) -> Vec<PathBuf> {
+    let _span = tracing::debug_span!("walk", threads).entered();
+
+    let search_paths: Vec<PathBuf> = match threads {
+        Some(threads) if threads != 1 => {
+            walk_parallel(starting_paths, extension_filter, threads, walk_options)
+                .into_iter()
+                .collect()
+        }
+        _ => starting_paths
+            .iter()
+            .flat_map(|starting_path| {
+                get_walk_builder(std::slice::from_ref(starting_path), walk_options)
+                    .build()
+                    .filter_map(Result::ok)
+                    .filter(|f| f.path().is_file() && is_walked(f.path(), extension_filter))
+                    .map(|file| file.path().to_owned())
+            })
+            .unique()
+            .collect(),
+    };
+
+    // the walk order depends on the platform and the threads, sort it so the output is always
+    // the same
+    let search_paths = search_paths.into_iter().sorted();
+
+    // a file found through a symlink and through its real path is only sorted once, otherwise it
+    // could be written twice at the same time
+    let search_paths: Vec<PathBuf> = if walk_options.follow_symlinks {
+        search_paths
+            .unique_by(|path| path.canonicalize().unwrap_or_else(|_| path.clone()))
+            .collect()
+    } else {
+        search_paths.collect()
+    };
+
+    tracing::debug!(files = search_paths.len(), "walked the starting paths");
+    search_paths
+}
+
+/// Walks the starting paths respecting the `.gitignore` and `.rustywindignore` files and skipping
+/// the hidden files, unless the `walk_options` say otherwise. The `.rustywindignore` files are
+/// always respected and the `.git` directories are always skipped
+fn get_walk_builder(starting_paths: &[PathBuf], walk_options: &WalkOptions) -> WalkBuilder {
+    let mut walk_builder = WalkBuilder::new(starting_paths.first().map_or(Path::new("."), |p| p));
+    for starting_path in starting_paths.iter().skip(1) {
+        walk_builder.add(starting_path);
+    }
+
+    walk_builder
+        .add_custom_ignore_filename(IGNORE_FILE_NAME)
+        .standard_filters(!walk_options.no_ignore)
+        .hidden(!walk_options.hidden)
+        .follow_links(walk_options.follow_symlinks)
+        .max_filesize(walk_options.max_filesize)
+        .filter_entry(|entry| entry.file_name() != ".git");
+
+    walk_builder
+}
+
+/// The files found by the threads are deduplicated in a shared set, the same file can be found
+/// from overlapping starting paths
+fn walk_parallel(
+    starting_paths: &[PathBuf],
+    extension_filter: &ExtensionFilter,
+    threads: usize,
+    walk_options: &WalkOptions,
+) -> HashSet<PathBuf> {
+    if starting_paths.is_empty() {
+        return HashSet::new();
+    }
+
+    let search_paths = Mutex::new(HashSet::new());
+
+    get_walk_builder(starting_paths, walk_options)
+        .threads(threads)
+        .build_parallel()
+        .run(|| {
+            Box::new(|file| {
+                if let Ok(file) = file {
+                    if file.path().is_file() && is_walked(file.path(), extension_filter) {
+                        let mut search_paths = search_paths.lock().unwrap();
+                        search_paths.insert(file.path().to_owned());
+                    }
+                }
+
+                WalkState::Continue
+            })
+        });
+
+    search_paths.into_inner().unwrap()
+}
+
+fn is_walked(path: &Path, extension_filter: &ExtensionFilter) -> bool {
+    let is_match = extension_filter.is_match(path);
+
+    if !is_match {
+        tracing::trace!(path = %path.display(), "filtered out by its extension");
+    }
+
+    is_match
+}
+
+/// Keeps the listed files that exist, a list from `git diff --name-only` can have deleted files
+pub(crate) fn get_search_paths_from_file_list(
+    files: &[PathBuf],
+    extension_filter: &ExtensionFilter,
+) -> Vec<PathBuf> {
+    files
         .iter()
-        .flat_map(|starting_path| {
-            WalkBuilder::new(starting_path)
-                .build()
-                .filter_map(Result::ok)
-                .filter(|f| f.path().is_file())
-                .map(|file| file.path().to_owned())
-        })
+        .filter(|file| file.is_file() && extension_filter.is_match(file))
+        .cloned()
         .unique()
+        .sorted()
         .collect()
 }
 
-fn get_ignored_files_from_cli(cli: &Cli) -> HashSet<PathBuf> {
+fn get_ignored_globs_from_cli(cli: &Cli) -> Result<Vec<Glob>> {
     match &cli.ignored_files {
-        Some(ignored_files) => ignored_files
-            .iter()
-            .map(|string| PathBuf::from_str(string))
-            .filter_map(Result::ok)
-            .map(std::fs::canonicalize)
-            .filter_map(Result::ok)
-            .collect(),
-        None => HashSet::new(),
+        Some(ignored_files) => parse_ignored_files(ignored_files),
+        None => Ok(vec![]),
     }
 }
 
+pub(crate) fn build_glob_set(globs: &[Glob]) -> Result<GlobSet> {
+    let mut glob_set = GlobSetBuilder::new();
+    for glob in globs {
+        glob_set.add(glob.clone());
+    }
+
+    Ok(glob_set.build()?)
+}
+
+/// A path that exists only matches itself once canonicalized, a path that doesn't exist is skipped.
+/// A glob, ex: `src/**/*.generated.html`, is relative to the current directory and `*` doesn't
+/// match across directories
+pub(crate) fn parse_ignored_files(ignored_files: &[String]) -> Result<Vec<Glob>> {
+    let mut globs = Vec::new();
+
+    for ignored_file in ignored_files {
+        // a file that exists is matched exactly, even if its name has glob characters
+        let glob = if let Ok(path) = canonicalize(ignored_file) {
+            escape_glob(&path.to_string_lossy())
+        } else if !ignored_file.contains(['*', '?', '[', '{']) {
+            continue;
+        } else if Path::new(ignored_file).is_absolute() {
+            ignored_file.to_string()
+        } else {
+            let current_dir = std::env::current_dir()?.canonicalize()?;
+            let current_dir = escape_glob(&current_dir.to_string_lossy());
+
+            format!("{current_dir}/{}", ignored_file.trim_start_matches("./"))
+        };
+
+        let glob = GlobBuilder::new(&glob)
+            .literal_separator(true)
+            .build()
+            .wrap_err_with(|| format!("Unable to parse the ignored files glob {ignored_file}"))
+            .with_suggestion(|| "Use a path or a glob like src/**/*.generated.html")?;
+
+        globs.push(glob);
+    }
+
+    Ok(globs)
+}
+
+/// Escapes the glob characters of a path, so it only matches itself
+fn escape_glob(path: &str) -> String {
+    path.chars()
+        .map(|char| match char {
+            '*' | '?' | '[' | ']' | '{' | '}' => format!("[{char}]"),
+            char => char.to_string(),
+        })
+        .collect()
+}
+
+/// Unlike the ignored files, a directory of `--fix-only` that doesn't exist is an error, otherwise
+/// a typo would silently refuse every write
+fn get_fix_only_from_cli(cli: &Cli) -> Result<Vec<PathBuf>> {
+    cli.fix_only
+        .iter()
+        .map(|dir| {
+            canonicalize(dir)
+                .wrap_err_with(|| format!("Unable to find the --fix-only directory {dir}"))
+                .with_suggestion(|| format!("Make sure the directory {dir} exists"))
+        })
+        .collect()
+}
+
+fn canonicalize(path: &str) -> Result<PathBuf> {
+    Ok(std::fs::canonicalize(PathBuf::from_str(path)?)?)
+}
+
 fn parse_custom_sorter(contents: Vec<String>) -> HashMap<String, usize> {
     contents
         .into_iter()
@@ -170,3 +2023,30 @@ fn parse_custom_sorter(contents: Vec<String>) -> HashMap<String, usize> {
         .map(|(index, class)| (class, index))
         .collect()
 }
+
+/// Builds a sorter with the `prepend` classes first, then the default sort order, then the `append`
+/// classes, a default class that is prepended or appended is moved to its new position
+fn parse_extended_sorter(
+    prepend: Vec<String>,
+    append: Vec<String>,
+    default_sorter: &HashMap<String, usize>,
+) -> HashMap<String, usize> {
+    let moved_classes: HashSet<&String> = prepend.iter().chain(append.iter()).collect();
+
+    let default_classes = default_sorter
+        .iter()
+        .sorted_by_key(|(_class, index)| **index)
+        .map(|(class, _index)| class)
+        .filter(|class| !moved_classes.contains(class))
+        .cloned()
+        .collect::<Vec<String>>();
+
+    parse_custom_sorter(
+        prepend
+            .iter()
+            .cloned()
+            .chain(default_classes)
+            .chain(append.iter().cloned())
+            .collect(),
+    )
+}
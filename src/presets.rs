@@ -0,0 +1,357 @@
+//! Built-in finders that can be combined, each one sorts the classes of a specific syntax
+
+use color_eyre::Help;
+use eyre::{Context, Result};
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::defaults::{RE, STRING_LITERAL_RE, TW_MERGE_FUNCTIONS};
+use crate::options::{count_finder_compilation, FinderRegex, RegexPair};
+
+/// Matches a bound class attribute in Vue, ex: `:class="{ 'px-2 flex': active }"`
+static VUE_CLASS_BINDING_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?:\bv-bind)?:class\s*=\s*(?:"[^"]*"|'[^']*')"#).unwrap());
+
+/// Matches an Angular `[ngClass]` binding, ex: `[ngClass]="{ 'px-2 flex': active }"`. The
+/// `[class.px-2]="active"` bindings are not matched, they only toggle one class
+static NG_CLASS_BINDING_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\[ngClass\]\s*=\s*(?:"[^"]*"|'[^']*')"#).unwrap());
+
+/// Matches the strings of a Vue or Angular class binding that are classes, the whole expression,
+/// the keys of an object, the elements of an array and the results of a ternary, ex:
+/// `['px-2 flex', active ? 'block' : '']`. A string is a class string when it's followed by a `,`,
+/// `]`, `:`, `}` or the end of the binding. The strings compared in a condition, ex:
+/// `mode === 'dark mode'`, and the first argument of a call are matched without a capture group, so
+/// they are left untouched
+static BINDING_CLASS_RE: Lazy<Regex> = Lazy::new(|| {
+    let classes = r#"(?:[_a-zA-Z0-9\.\s\-:/!]|\[[^\]"']*\])+"#;
+    let string = r#"(?:'[^']*'|"[^"]*")"#;
+
+    Regex::new(&format!(
+        r#"{string}\s*[=!]==?|(?:[=!]==?|\()\s*{string}|'({classes})'\s*[,\]:}}"]|"({classes})"\s*[,\]:}}']"#
+    ))
+    .unwrap()
+});
+
+/// Matches a bound class attribute in Alpine.js, ex: `x-bind:class="{ 'px-2 flex': open }"` or
+/// the `:class` shorthand
+static ALPINE_CLASS_BINDING_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?:\bx-bind)?:class\s*=\s*(?:"[^"]*"|'[^']*')"#).unwrap());
+
+/// Matches the string of an Alpine.js class binding when it's the whole expression, ex:
+/// `:class="'px-2 flex'"`, or a quoted key of its object, ex: `{ 'px-2 flex': open }`. A key has to
+/// follow the `{` or a `,` so the strings of the conditions, ex: `open ? 'a' : 'b'`, are not keys
+static ALPINE_CLASS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"=\s*(?:"\s*'((?:[_a-zA-Z0-9\.\s\-:/!]|\[[^\]"']*\])+)'\s*"|'\s*"((?:[_a-zA-Z0-9\.\s\-:/!]|\[[^\]"']*\])+)"\s*')|[{,]\s*(?:'((?:[_a-zA-Z0-9\.\s\-:/!]|\[[^\]"']*\])+)'|"((?:[_a-zA-Z0-9\.\s\-:/!]|\[[^\]"']*\])+)")\s*:"#,
+    )
+    .unwrap()
+});
+
+/// Matches the tuple form of a Leptos class attribute up to the end of its string literal,
+/// ex: `class=("px-2 flex", move || active())`
+static LEPTOS_CLASS_TUPLE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\bclass\s*=\s*\(\s*"[^"]*""#).unwrap());
+
+/// Matches a HEEx `class={...}` expression binding, it can contain one level of nested braces,
+/// ex: `class={["px-2 flex", @active && "underline"]}`
+static HEEX_CLASS_BINDING_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bclass\s*=\s*\{(?:[^{}]|\{[^{}]*\})*\}").unwrap());
+
+/// The nesting depth of the braces matched in a Maud `html!` body, a deeper body is not sorted
+const MAUD_MAX_DEPTH: usize = 16;
+
+/// Matches the body of a Maud `html! { ... }` macro, the regex crate has no recursion so the nested
+/// braces are matched up to `MAUD_MAX_DEPTH`, the braces in string literals are not counted
+static MAUD_HTML_MACRO_RE: Lazy<Regex> = Lazy::new(|| {
+    let string = r#""(?:[^"\\]|\\.)*""#;
+    let mut block = format!(r#"\{{(?:[^{{}}"]|{string})*\}}"#);
+
+    for _ in 0..MAUD_MAX_DEPTH {
+        block = format!(r#"\{{(?:[^{{}}"]|{string}|{block})*\}}"#);
+    }
+
+    Regex::new(&format!(r"\bhtml!\s*{block}")).unwrap()
+});
+
+/// Matches the class chain of a Maud element, with or without its tag and id, followed by the
+/// element body, its attributes or the `;` of a void element, ex: `div#main.px-2.flex {`. The
+/// element has to start a statement, after a line break, a brace, a `;`, a string or a splice, so
+/// the Rust expressions, ex: `@if self.state.active {`, are not elements. The chain is in the
+/// `dotted` capture group so its classes are sorted as a dotted chain
+static MAUD_CLASS_CHAIN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?m)(?:^|[{};")])\s*(?:[a-zA-Z][\w-]*)?(?:#[\w-]+)?(?P<dotted>(?:\.(?:[\w-]+|"[^"]*")(?:\[[^\]]*\])?)+)(?:#[\w-]+)?\s*(?:\{|;|[\w-]+=)"#,
+    )
+    .unwrap()
+});
+
+/// Matches a Tailwind `@apply` directive, the classes are captured without the trailing whitespace
+/// and the (Tailwind v2) `!important`, ex: `@apply flex px-2;`
+static CSS_APPLY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"@apply\s+([^;{}]*?[^;{}\s])(?:\s+!important)?\s*;").unwrap());
+
+/// Matches a `className` or `class` attribute set to a template literal, the `${...}`
+/// interpolations can contain one level of nested braces, ex: `className={`flex ${a ? 'px-2' : ''}`}`
+static TEMPLATE_LITERAL_CLASS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\bclass(?:Name)?\s*=\s*\{\s*`(?:[^`$\\]|\\.|\$\{(?:[^{}]|\{[^{}]*\})*\}|\$)*`\s*\}",
+    )
+    .unwrap()
+});
+
+/// Matches a `className` or `class` attribute set to a `+` concatenation of string literals and
+/// simple operands, like identifiers, member accesses and calls without nested parentheses,
+/// ex: `className={"flex " + base + " px-2"}`
+static CONCATENATION_CLASS_RE: Lazy<Regex> = Lazy::new(|| {
+    let operand = r#"(?:"[^"]*"|'[^']*'|[\w.$]+(?:\([^()"']*\))?)"#;
+
+    Regex::new(&format!(
+        r"\bclass(?:Name)?\s*=\s*\{{\s*{operand}(?:\s*\+\s*{operand})+\s*\}}"
+    ))
+    .unwrap()
+});
+
+/// Matches one string literal of a concatenation, the classes are captured without the whitespace
+/// around them, it's kept so the pieces are still separated once joined, ex: `"flex " + base`
+static CONCATENATION_PART_RE: Lazy<Regex> = Lazy::new(|| {
+    let classes =
+        r#"(?:[_a-zA-Z0-9\.\-:/!]|\[[^\]"']*\])+(?:\s+(?:[_a-zA-Z0-9\.\-:/!]|\[[^\]"']*\])+)*"#;
+
+    Regex::new(&format!(r#""\s*({classes})\s*"|'\s*({classes})\s*'"#)).unwrap()
+});
+
+/// Matches the parts of a template literal, only the runs of whole classes between the
+/// interpolations are captured. The start of the attribute, the `${...}` interpolations and the
+/// classes that are glued to an interpolation, ex: `bg-${color}-500`, are matched without a capture
+/// group so they are left untouched
+static TEMPLATE_LITERAL_PART_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^[^`]*`|[^`$\s]*\$\{(?:[^{}]|\{[^{}]*\})*\}[^`$\s]*|([^`$\s]+(?:\s+[^`$\s]+)*)(?:\s|`)",
+    )
+    .unwrap()
+});
+
+/// Matches a quoted `class` attribute that can contain Handlebars or Jinja `{{ ... }}`
+/// interpolations and Jinja `{% ... %}` tags and `{# ... #}` comments, ex: `class="flex {{ extra }}"`
+static TEMPLATE_CLASS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?s)\bclass\s*=\s*(?:"(?:[^"{]|\{\{.*?\}\}|\{%.*?%\}|\{\#.*?\#\}|\{)*"|'(?:[^'{]|\{\{.*?\}\}|\{%.*?%\}|\{\#.*?\#\}|\{)*')"#,
+    )
+    .unwrap()
+});
+
+/// Matches the parts of a templated class attribute, only the runs of whole classes between the
+/// interpolations and tags are captured. The start of the attribute, the interpolations, the classes
+/// that are glued to an interpolation, ex: `bg-{{ color }}-500`, and the tags are matched without a
+/// capture group so they are left untouched. A tag can end a run, ex: `px-2{% endif %}`
+static TEMPLATE_PART_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?s)^[^"']*["']|[^\s"'{}]*\{\{.*?\}\}[^\s"'{}]*|\{%.*?%\}|\{\#.*?\#\}|([^\s"'{}]+(?:\s+[^\s"'{}]+)*)(?:\s|["']|\{%.*?%\}|\{\#.*?\#\})"#,
+    )
+    .unwrap()
+});
+
+/// Matches a `cva()` call of class-variance-authority, the variants are objects inside of the call
+/// so it only has to handle one level of nested parentheses, ex: `cva("px-2", { variants: {} })`
+static CVA_CALL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bcva\((?:[^()]|\([^()]*\))*\)").unwrap());
+
+/// Matches the string literals of a `cva()` call, a quoted object key is matched without a capture
+/// group so only the values are sorted, ex: `{ "data-state": "px-2 flex" }`
+static CVA_STRING_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r#"(?:"[^"]*"|'[^']*')\s*:|{}"#,
+        STRING_LITERAL_RE.as_str()
+    ))
+    .unwrap()
+});
+
+/// Matches either a fenced code block of Markdown, without a capture group so it is left untouched,
+/// or a regular class attribute. An unclosed fence runs until the end of the document
+static MARKDOWN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"(?ms:^[ \t]*```.*?(?:^[ \t]*```|\z)|^[ \t]*~~~.*?(?:^[ \t]*~~~|\z))|{}",
+        RE.as_str()
+    ))
+    .unwrap()
+});
+
+/// The finder used for `.css` and `.scss` files when no custom finder is set
+pub static CSS_FINDER: Lazy<FinderRegex> = Lazy::new(|| {
+    count_finder_compilation();
+    FinderRegex::CustomRegexEntries(vec![get_css_regex_pair()])
+});
+
+/// The finder used for `.vue` files when no custom finder is set
+pub static VUE_FINDER: Lazy<FinderRegex> = Lazy::new(|| {
+    count_finder_compilation();
+    FinderRegex::CustomRegexEntries(vec![get_default_regex_pair(), get_vue_regex_pair()])
+});
+
+/// The finder used for `.heex` and `.eex` files when no custom finder is set
+pub static HEEX_FINDER: Lazy<FinderRegex> = Lazy::new(|| {
+    count_finder_compilation();
+    FinderRegex::CustomRegexEntries(vec![get_default_regex_pair(), get_heex_regex_pair()])
+});
+
+/// The finder used for `.tsx` and `.jsx` files when no custom finder is set
+pub static TSX_FINDER: Lazy<FinderRegex> = Lazy::new(|| {
+    count_finder_compilation();
+    FinderRegex::CustomRegexEntries(vec![
+        get_default_regex_pair(),
+        get_tsx_regex_pair(),
+        get_concatenation_regex_pair(),
+    ])
+});
+
+/// Sorts regular `class` and `className` attributes, the same as `FinderRegex::DefaultRegex`
+pub fn get_default_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: RE.clone(),
+        class_regex: RE.clone(),
+    }
+}
+
+/// Sorts every string literal argument of the class functions, ex: `clsx("flex", cond && "px-2")`
+pub fn get_clsx_regex_pair(class_functions: &[&str]) -> Result<RegexPair> {
+    get_function_arguments_regex_pair(class_functions)
+        .wrap_err("Unable to build the regex for the class functions")
+        .with_suggestion(|| "Make sure the classFunctions in your config file are valid names")
+}
+
+/// Sorts every string literal argument of tailwind-merge calls, ex: `twMerge("flex px-2", variant)`
+pub fn get_tw_merge_regex_pair() -> RegexPair {
+    get_function_arguments_regex_pair(&TW_MERGE_FUNCTIONS)
+        .expect("the tailwind-merge function names are valid")
+}
+
+/// Finds the calls of the functions and sorts their string literal arguments, the other arguments
+/// are left untouched
+fn get_function_arguments_regex_pair(functions: &[&str]) -> Result<RegexPair> {
+    let functions = functions.iter().map(|name| regex::escape(name)).join("|");
+
+    // matches up to one level of nested parentheses inside the call, ex: `clsx("flex", fn(a))`
+    let container_regex = Regex::new(&format!(r"\b(?:{functions})\((?:[^()]|\([^()]*\))*\)"))?;
+
+    Ok(RegexPair {
+        container_regex,
+        class_regex: STRING_LITERAL_RE.clone(),
+    })
+}
+
+/// Sorts the class strings of Vue `:class` and `v-bind:class` bindings, this covers the string,
+/// object (`{ 'px-2 flex': active }`) and array (`['flex', 'px-2']`) syntaxes, the strings of the
+/// conditions are left untouched, see `BINDING_CLASS_RE`
+pub fn get_vue_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: VUE_CLASS_BINDING_RE.clone(),
+        class_regex: BINDING_CLASS_RE.clone(),
+    }
+}
+
+/// Sorts the class strings of Angular `[ngClass]` bindings, this covers the string
+/// (`"'px-2 flex'"`), object and array syntaxes, the strings of the conditions are left untouched,
+/// see `BINDING_CLASS_RE`. The regular `class="..."` attributes are sorted by the default finder
+pub fn get_angular_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: NG_CLASS_BINDING_RE.clone(),
+        class_regex: BINDING_CLASS_RE.clone(),
+    }
+}
+
+/// Sorts the classes of Alpine.js `:class` and `x-bind:class` bindings, the string form
+/// (`"'px-2 flex'"`) and the keys of the object form (`{ 'px-2 flex': open }`), the conditions are
+/// left untouched. The static `class="..."` attributes are sorted by the default finder
+pub fn get_alpine_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: ALPINE_CLASS_BINDING_RE.clone(),
+        class_regex: ALPINE_CLASS_RE.clone(),
+    }
+}
+
+/// Sorts the string literal of the Leptos `class=("px-2 flex", move || active())` tuple form, the
+/// condition is left untouched. The `class="..."` form is sorted by the default finder and a
+/// `class:px-2=active` toggle only has one class, so there is nothing to sort
+pub fn get_leptos_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: LEPTOS_CLASS_TUPLE_RE.clone(),
+        class_regex: STRING_LITERAL_RE.clone(),
+    }
+}
+
+/// Sorts the string literals inside of HEEx `class={...}` bindings, ex: `class={["px-2 flex", @a]}`,
+/// the assigns and the strings with `#{...}` interpolations are left untouched. The static
+/// `class="..."` attributes are sorted by the default finder
+pub fn get_heex_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: HEEX_CLASS_BINDING_RE.clone(),
+        class_regex: STRING_LITERAL_RE.clone(),
+    }
+}
+
+/// Sorts the dotted class chains of Maud elements in the `html! { ... }` macros, ex:
+/// `div.px-2.flex { }` or `.px-2.flex { }`, the `class="..."` attributes are sorted by the default
+/// finder
+pub fn get_maud_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: MAUD_HTML_MACRO_RE.clone(),
+        class_regex: MAUD_CLASS_CHAIN_RE.clone(),
+    }
+}
+
+/// Sorts the classes of the Tailwind `@apply` directives in CSS, ex: `@apply flex px-2 py-1;`
+pub fn get_css_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: CSS_APPLY_RE.clone(),
+        class_regex: CSS_APPLY_RE.clone(),
+    }
+}
+
+/// Sorts the classes of a JSX `className={`...`}` template literal, the `${...}` interpolations
+/// are left untouched and stay where they are, only the classes between them are sorted
+pub fn get_tsx_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: TEMPLATE_LITERAL_CLASS_RE.clone(),
+        class_regex: TEMPLATE_LITERAL_PART_RE.clone(),
+    }
+}
+
+/// Sorts each string literal of a JSX `className={"flex " + base + " px-2"}` concatenation on its
+/// own, the classes are joined at runtime so they can't move from one string to another. The `+`
+/// operators and the other operands are left untouched
+pub fn get_concatenation_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: CONCATENATION_CLASS_RE.clone(),
+        class_regex: CONCATENATION_PART_RE.clone(),
+    }
+}
+
+/// Sorts the classes of Handlebars and Jinja `class` attributes, the `{{ ... }}` interpolations and
+/// `{% ... %}` tags stay where they are and only the classes between them are sorted, so the
+/// classes never move in or out of a conditional block
+pub fn get_handlebars_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: TEMPLATE_CLASS_RE.clone(),
+        class_regex: TEMPLATE_PART_RE.clone(),
+    }
+}
+
+/// Sorts the base classes and every variant value of a class-variance-authority `cva()` call,
+/// ex: `cva("px-2 flex", { variants: { size: { sm: "text-sm py-1" } } })`, the keys are left as is
+pub fn get_cva_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: CVA_CALL_RE.clone(),
+        class_regex: CVA_STRING_RE.clone(),
+    }
+}
+
+/// Sorts the class attributes of Markdown and MDX files, except for the ones inside of fenced code
+/// blocks, so the example snippets are not changed. It replaces the default finder
+pub fn get_mdx_regex_pair() -> RegexPair {
+    RegexPair {
+        container_regex: MARKDOWN_RE.clone(),
+        class_regex: MARKDOWN_RE.clone(),
+    }
+}
@@ -1,25 +1,116 @@
 use pretty_assertions::assert_eq;
 
-use super::*;
-use std::collections::HashSet;
-use std::path::Path;
+use crate::cache::Cache;
+use crate::cli::Cli;
+use crate::defaults::{CLASS_FUNCTIONS, RE_IGNORE_CASE};
+use crate::options::{
+    build_glob_set, find_config_file, get_allow_duplicates_from_cli, get_cache_key,
+    get_file_regex_from_config, get_files_matching, get_join_separator_from_cli,
+    get_search_paths_from_file_list, get_search_paths_from_starting_paths, get_sorter_from_config,
+    get_write_mode_from_cli, parse_config_file, parse_custom_regex, parse_file_list,
+    parse_ignored_files, parse_sort_order_file, validate_config_file_from_cli,
+    validate_stdin_from_cli, ClassSeparator, CustomClassOrder, CustomClassPosition,
+    ExtensionFilter, FinderRegex, IgnoredClassPosition, Options, Outcome, RegexPair, SortOrder,
+    Sorter, TailwindVersion, WalkOptions, WriteMode, FINDER_COMPILATIONS,
+};
+use crate::utils::{self, ClassChange};
+use crate::{git, init, json, presets};
+use clap::Parser;
+use globset::GlobSet;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A directory in the temp directory for the tests that need files, it's removed when it's dropped
+/// so a failing test doesn't leave it behind
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("rustywind-{name}-test-{}", std::process::id()));
+        // a directory left by a test that was killed
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+
+        TestDir(path)
+    }
+}
+
+impl std::ops::Deref for TestDir {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
 
 fn default_options_for_test() -> Options {
     Options {
         stdin: None,
+        range: None,
+        output: None,
         write_mode: WriteMode::ToConsole,
-        ignored_files: HashSet::new(),
+        json_plan: false,
+        json_check: false,
+        exit_code_on_change: false,
+        strict: false,
+        ignored_files: GlobSet::empty(),
+        ignored_globs: vec![],
+        fix_only: vec![],
+        config_files: vec![],
         regex: FinderRegex::DefaultRegex,
+        file_regex: vec![],
+        json_field: None,
         sorter: Sorter::DefaultSorter,
+        tailwind_version: TailwindVersion::V3,
+        prefix: None,
         starting_paths: vec![Path::new(".").to_owned()],
         search_paths: vec![Path::new(".").to_owned()],
+        extension_filter: ExtensionFilter::default(),
+        watch: false,
+        walk_options: WalkOptions::default(),
         allow_duplicates: false,
+        lowercase_classes: false,
+        preserve_whitespace: false,
+        warn_duplicates: false,
+        fail_on_duplicate: false,
+        preserve_custom_position: false,
+        ignored_classes: vec![],
+        ignored_class_position: IgnoredClassPosition::First,
+        max_line_length: None,
+        tab_width: 4,
+        use_tabs: false,
+        group_prefixes: vec![],
+        pin_marker_classes: false,
+        group_separator: "  ".to_string(),
+        custom_class_order: CustomClassOrder::Original,
+        custom_class_position: CustomClassPosition::Last,
+        class_separator: ClassSeparator::Whitespace,
+        join_separator: " ".to_string(),
+        variant_order: vec![],
+        backup: false,
+        restage: false,
+        interactive: false,
+        only_changed: false,
+        color: false,
+        stats: false,
+        quiet: false,
+        cache: None,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::default_options_for_test;
+    use super::*;
+    use pretty_assertions::assert_eq;
 
     #[test]
     fn test_sort_file_contents() {
@@ -222,6 +313,47 @@ fn test_sort_file_contents_with_spaces_newlines_and_custom_classes() {
     )
 }
 
+#[test]
+fn test_sort_file_contents_with_every_quote_style() {
+    let file_contents = r#"
+<div class="px-2 flex"></div>
+<div class='px-2 flex'></div>
+<div class=`px-2 flex`></div>
+<div class=flex><span class=px-2 >
+"#;
+
+    let expected_outcome = r#"
+<div class="flex px-2"></div>
+<div class='flex px-2'></div>
+<div class=`flex px-2`></div>
+<div class=flex><span class=px-2 >
+"#;
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        expected_outcome
+    );
+
+    // an unquoted value is one class, only the class itself is captured
+    let options = Options {
+        lowercase_classes: true,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents("<div class=FLEX>", &options),
+        "<div class=flex>"
+    );
+    assert_eq!(
+        utils::sort_file_contents(r#"<div id="main" class=FLEX>"#, &options),
+        r#"<div id="main" class=flex>"#
+    );
+
+    // the unquoted values are only class attributes of a tag, the JS assignments are left as is
+    let js = "el.className = Zebra ? a : b;\nconst value = { class = Foo };\nx.class=Bar;\n";
+    assert_eq!(utils::sort_file_contents(js, &options), Cow::Borrowed(js));
+}
+
 #[test]
 fn test_sort_file_contents_with_responsive_classes() {
     let file_contents = r#"
@@ -271,3 +403,2848 @@ fn test_sort_file_contents_with_variant_classes() {
         expected_outcome
     )
 }
+
+#[test]
+fn test_sort_file_contents_with_clsx_function_calls() {
+    let file_contents = r#"
+<div className="px-2 flex">
+    <a className={clsx("px-2 flex", active ? "py-2 block" : 'inline py-4', { "mt-2 mb-2": open })} />
+    <b className={cn(`px-2`, classes.root, fn(a, "b"), "underline px-2 flex")} />
+</div>
+"#;
+
+    let expected_outcome = r#"
+<div className="flex px-2">
+    <a className={clsx("flex px-2", active ? "block py-2" : 'inline py-4', { "mt-2 mb-2": open })} />
+    <b className={cn(`px-2`, classes.root, fn(a, "b"), "flex px-2 underline")} />
+</div>
+"#
+    .to_string();
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![
+            presets::get_default_regex_pair(),
+            presets::get_clsx_regex_pair(&CLASS_FUNCTIONS).unwrap(),
+        ]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    )
+}
+
+#[test]
+fn test_sort_file_contents_with_custom_class_functions() {
+    let file_contents = r#"<a class={tw("px-2 flex")} b={clsx("px-2 flex")}></a>"#;
+    let expected_outcome = r#"<a class={tw("flex px-2")} b={clsx("px-2 flex")}></a>"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(
+            vec![presets::get_clsx_regex_pair(&["tw"]).unwrap()],
+        ),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    )
+}
+
+#[test]
+fn test_options_builder() {
+    let options = Options::builder()
+        .custom_regex(r#"\bclass="([^"]*)"()"#)
+        .unwrap()
+        .sort_order(vec!["px-2".to_string(), "flex".to_string()])
+        .allow_duplicates(true)
+        .build();
+
+    assert_eq!(
+        crate::sort_classes_in_str(r#"<a class="flex px-2 flex">"#, &options),
+        r#"<a class="px-2 flex flex">"#
+    );
+
+    assert!(Options::builder().custom_regex("class=(.*").is_err());
+}
+
+#[test]
+fn test_sort_file_contents_with_changes_only_returns_reordered_classes() {
+    let file_contents = r#"<a class="px-2 flex"><b class="flex px-2"><i class='underline block'>"#;
+
+    let (sorted_content, changes) =
+        utils::sort_file_contents_with_changes(file_contents, &default_options_for_test());
+
+    assert_eq!(
+        sorted_content,
+        r#"<a class="flex px-2"><b class="flex px-2"><i class='block underline'>"#
+    );
+
+    assert_eq!(
+        changes,
+        vec![
+            ClassChange {
+                before: "px-2 flex".to_string(),
+                after: "flex px-2".to_string(),
+                line: 1,
+                column: 4,
+            },
+            ClassChange {
+                before: "underline block".to_string(),
+                after: "block underline".to_string(),
+                line: 1,
+                column: 46,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_class_changes_have_the_line_and_column_of_the_attribute() {
+    let file_contents = "<div>\n  <a class=\"px-2 flex\">é <b class=\"flex px-2\"></b> <i class=\"mt-2 py-2\"></i></a>\n</div>\n";
+
+    let (_, changes) =
+        utils::sort_file_contents_with_changes(file_contents, &default_options_for_test());
+
+    let positions: Vec<(usize, usize)> = changes
+        .iter()
+        .map(|change| (change.line, change.column))
+        .collect();
+    assert_eq!(positions, vec![(2, 6), (2, 55)]);
+
+    // inside of a container, the positions are in the whole file
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![RegexPair {
+            container_regex: regex::Regex::new(r"(?s)clsx\((.*?)\)").unwrap(),
+            class_regex: regex::Regex::new(r#""([^"]*)""#).unwrap(),
+        }]),
+        ..default_options_for_test()
+    };
+
+    let (_, changes) = utils::sort_file_contents_with_changes(
+        "const a = 1;\nconst b = clsx(\"block\", \"px-2 flex\",\n  \"mt-2 py-2\");\n",
+        &options,
+    );
+
+    let positions: Vec<(usize, usize)> = changes
+        .iter()
+        .map(|change| (change.line, change.column))
+        .collect();
+    assert_eq!(positions, vec![(2, 25), (3, 3)]);
+}
+
+#[test]
+fn test_sort_file_contents_with_alphabetical_custom_classes() {
+    let file_contents = r#"<div class="zebra px-2 md:foo apple flex mango"></div>"#;
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        r#"<div class="flex px-2 zebra apple mango md:foo"></div>"#
+    );
+
+    let options = Options {
+        custom_class_order: CustomClassOrder::Alphabetical,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="flex px-2 apple mango md:foo zebra"></div>"#
+    );
+}
+
+#[test]
+fn test_search_paths_with_no_ignore_and_hidden() {
+    let dir = TestDir::new("no-ignore");
+    fs::create_dir_all(dir.join("dist")).unwrap();
+    fs::create_dir_all(dir.join(".storybook")).unwrap();
+    fs::create_dir_all(dir.join(".git")).unwrap();
+
+    fs::write(dir.join(".ignore"), "dist/\n").unwrap();
+    fs::write(dir.join(".rustywindignore"), "skip.html\n").unwrap();
+    fs::write(dir.join("index.html"), "").unwrap();
+    fs::write(dir.join("skip.html"), "").unwrap();
+    fs::write(dir.join(".hidden.html"), "").unwrap();
+    fs::write(dir.join(".storybook/preview.html"), "").unwrap();
+    fs::write(dir.join("dist/index.html"), "").unwrap();
+    fs::write(dir.join(".git/index.html"), "").unwrap();
+
+    let search_paths = |no_ignore, hidden, threads| {
+        let extension_filter = ExtensionFilter {
+            include: vec!["html".to_string()],
+            ..ExtensionFilter::default()
+        };
+
+        get_search_paths_from_starting_paths(
+            std::slice::from_ref(&dir),
+            &extension_filter,
+            threads,
+            &WalkOptions {
+                no_ignore,
+                hidden,
+                ..WalkOptions::default()
+            },
+        )
+    };
+
+    let default = search_paths(false, false, None);
+    let no_ignore = search_paths(true, false, None);
+    let hidden = search_paths(false, true, None);
+    let both = search_paths(true, true, None);
+    let both_in_parallel = search_paths(true, true, Some(2));
+
+    assert_eq!(default, vec![dir.join("index.html")]);
+    assert_eq!(
+        no_ignore,
+        vec![dir.join("dist/index.html"), dir.join("index.html")]
+    );
+    assert_eq!(
+        hidden,
+        vec![
+            dir.join(".hidden.html"),
+            dir.join(".storybook/preview.html"),
+            dir.join("index.html"),
+        ]
+    );
+
+    // the .rustywindignore files are still respected and the .git directory is skipped
+    let expected = vec![
+        dir.join(".hidden.html"),
+        dir.join(".storybook/preview.html"),
+        dir.join("dist/index.html"),
+        dir.join("index.html"),
+    ];
+    assert_eq!(both, expected);
+    assert_eq!(both_in_parallel, expected);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_search_paths_follow_symlinks() {
+    let dir = TestDir::new("symlinks");
+    let templates = dir.join("templates");
+    let shared = TestDir::new("shared");
+    fs::create_dir_all(&templates).unwrap();
+
+    fs::write(templates.join("index.html"), "").unwrap();
+    fs::write(shared.join("card.html"), "").unwrap();
+    std::os::unix::fs::symlink(&*shared, dir.join("shared")).unwrap();
+    std::os::unix::fs::symlink(&templates, dir.join("linked")).unwrap();
+    std::os::unix::fs::symlink(&*dir, templates.join("loop")).unwrap();
+
+    let search_paths = |follow_symlinks, threads| {
+        get_search_paths_from_starting_paths(
+            std::slice::from_ref(&dir),
+            &ExtensionFilter::default(),
+            threads,
+            &WalkOptions {
+                follow_symlinks,
+                ..WalkOptions::default()
+            },
+        )
+    };
+
+    let not_followed = search_paths(false, None);
+    let followed = search_paths(true, None);
+    let followed_in_parallel = search_paths(true, Some(2));
+
+    assert_eq!(not_followed, vec![templates.join("index.html")]);
+
+    // templates/index.html is also found as linked/index.html, it is only sorted once
+    let expected = vec![dir.join("linked/index.html"), dir.join("shared/card.html")];
+    assert_eq!(followed, expected);
+    assert_eq!(followed_in_parallel, expected);
+}
+
+#[test]
+fn test_max_filesize_skips_the_larger_files() {
+    let dir = TestDir::new("filesize");
+
+    let bundle = format!(r#"<div class="px-2 flex">{}</div>"#, "x".repeat(100));
+    fs::write(dir.join("small.html"), r#"<div class="px-2 flex"></div>"#).unwrap();
+    fs::write(dir.join("bundle.html"), &bundle).unwrap();
+
+    let walk_options = WalkOptions {
+        max_filesize: Some(64),
+        ..WalkOptions::default()
+    };
+
+    let search_paths = get_search_paths_from_starting_paths(
+        std::slice::from_ref(&dir),
+        &ExtensionFilter::default(),
+        None,
+        &walk_options,
+    );
+
+    // a file that is not walked, ex: from --files-from, is still skipped before being read
+    let options = Options {
+        walk_options,
+        ..default_options_for_test()
+    };
+    let outcome = options.apply(&dir.join("bundle.html"));
+    let has_classes = options.has_classes(&dir.join("bundle.html"));
+
+    assert_eq!(search_paths, vec![dir.join("small.html")]);
+    assert!(matches!(outcome, Ok(Outcome::Skipped)));
+    assert!(!has_classes.unwrap());
+}
+
+#[test]
+fn test_search_paths_respect_rustywindignore_files() {
+    let dir = TestDir::new("ignore");
+    let nested = dir.join("nested");
+    fs::create_dir_all(&nested).unwrap();
+
+    fs::write(dir.join(".rustywindignore"), "*.generated.html\n").unwrap();
+    fs::write(nested.join(".rustywindignore"), "skip.html\n").unwrap();
+    fs::write(dir.join("index.html"), "").unwrap();
+    fs::write(dir.join("skip.html"), "").unwrap();
+    fs::write(dir.join("page.generated.html"), "").unwrap();
+    fs::write(nested.join("skip.html"), "").unwrap();
+    fs::write(nested.join("nested.generated.html"), "").unwrap();
+
+    let mut search_paths = get_search_paths_from_starting_paths(
+        std::slice::from_ref(&dir),
+        &ExtensionFilter::default(),
+        None,
+        &WalkOptions::default(),
+    );
+    search_paths.sort();
+
+    assert_eq!(
+        search_paths,
+        vec![dir.join("index.html"), dir.join("skip.html")]
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_tailwind_v4_sort_order() {
+    let file_contents =
+        r#"<div class="p-4 size-8 text-balance flex relative mt-2 shrink-0 px-2 custom"></div>"#;
+
+    let options = Options {
+        tailwind_version: TailwindVersion::V4,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="relative mt-2 flex size-8 shrink-0 p-4 px-2 text-balance custom"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_important_classes() {
+    let file_contents = r#"<div class="!px-2 custom flex !mt-4 block"></div>"#;
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        r#"<div class="block flex !px-2 !mt-4 custom"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_important_variant_classes() {
+    let file_contents =
+        r#"<div class="md:!px-2 hover:!flex md:block !inline md:!flex px-2"></div>"#;
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        r#"<div class="!inline px-2 md:block md:!flex md:!px-2 hover:!flex"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_tailwind_prefix() {
+    let file_contents = r#"<div class="tw-px-2 md:!tw-px-2 custom md:tw-block flex -tw-mt-2 !tw-inline tw-mt-2"></div>"#;
+
+    let options = Options {
+        prefix: Some("tw-".to_string()),
+        ..default_options_for_test()
+    };
+
+    // the classes without the prefix, ex: `flex`, are custom classes
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="!tw-inline tw-px-2 tw-mt-2 -tw-mt-2 md:tw-block md:!tw-px-2 custom flex"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_vue_class_bindings() {
+    let file_contents = r#"
+<template>
+  <div class="px-2 flex" :class="{ 'px-2 flex': active, 'underline block': other }">
+    <a v-bind:class="['px-2 flex', isActive ? 'py-2 block' : '']"></a>
+    <b :class="'px-2 flex'" :style="'px-2 flex'"></b>
+    <i :class="isActive"></i>
+    <p :class='["px-2 flex", "py-2 block"]'></p>
+  </div>
+</template>
+"#;
+
+    let expected_outcome = r#"
+<template>
+  <div class="flex px-2" :class="{ 'flex px-2': active, 'block underline': other }">
+    <a v-bind:class="['flex px-2', isActive ? 'block py-2' : '']"></a>
+    <b :class="'flex px-2'" :style="'px-2 flex'"></b>
+    <i :class="isActive"></i>
+    <p :class='["flex px-2", "block py-2"]'></p>
+  </div>
+</template>
+"#;
+
+    let options = default_options_for_test();
+    let regex = options.regex_for_path(Path::new("Component.vue"));
+
+    assert_eq!(
+        utils::sort_file_contents_with_regex(file_contents, regex, &options).0,
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_vue_and_angular_conditions_untouched() {
+    let file_contents = r#"
+<div :class="{ active: mode === 'b a a', 'px-2 flex': 'b a' !== mode }"></div>
+<div :class="mode == 'dark mode' ? 'px-2 flex' : 'py-2 block'"></div>
+<div :class="[modes.includes('b a a') ? 'px-2 flex' : '', 'py-2 block']"></div>
+<div [ngClass]="{ 'px-2 flex': mode === 'x y x', 'py-2 block': mode != 'y x' }"></div>
+<div [ngClass]="mode === 'y x' ? 'px-2 flex' : 'py-2 block'"></div>
+"#;
+
+    let expected_outcome = r#"
+<div :class="{ active: mode === 'b a a', 'flex px-2': 'b a' !== mode }"></div>
+<div :class="mode == 'dark mode' ? 'flex px-2' : 'block py-2'"></div>
+<div :class="[modes.includes('b a a') ? 'flex px-2' : '', 'block py-2']"></div>
+<div [ngClass]="{ 'flex px-2': mode === 'x y x', 'block py-2': mode != 'y x' }"></div>
+<div [ngClass]="mode === 'y x' ? 'flex px-2' : 'block py-2'"></div>
+"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![
+            presets::get_vue_regex_pair(),
+            presets::get_angular_regex_pair(),
+        ]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_angular_class_bindings() {
+    let file_contents = r#"
+<div class="px-2 flex" [ngClass]="'py-2 block'">
+  <a [ngClass]="{ 'px-2 flex': active, 'underline block': other }"></a>
+  <b [ngClass]="['px-2 flex', isActive ? 'py-2 block' : '']" [class.px-2]="isActive"></b>
+  <i [class.flex]="'px-2 flex'" [ngClass]="classes"></i>
+</div>
+"#;
+
+    let expected_outcome = r#"
+<div class="flex px-2" [ngClass]="'block py-2'">
+  <a [ngClass]="{ 'flex px-2': active, 'block underline': other }"></a>
+  <b [ngClass]="['flex px-2', isActive ? 'block py-2' : '']" [class.px-2]="isActive"></b>
+  <i [class.flex]="'px-2 flex'" [ngClass]="classes"></i>
+</div>
+"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![
+            presets::get_default_regex_pair(),
+            presets::get_angular_regex_pair(),
+        ]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_alpine_class_bindings() {
+    let file_contents = r#"
+<div class="px-2 flex" :class="{ 'px-2 flex': open, 'underline block': !open, 'py-2': mode === 'dark mode' }">
+  <a x-bind:class="'px-2 flex'" :class='{ "py-2 block": open ? "px-2 flex" : "" }'></a>
+  <b :class="open ? 'px-2 flex' : 'py-2 block'"></b>
+</div>
+"#;
+
+    let expected_outcome = r#"
+<div class="flex px-2" :class="{ 'flex px-2': open, 'block underline': !open, 'py-2': mode === 'dark mode' }">
+  <a x-bind:class="'flex px-2'" :class='{ "block py-2": open ? "px-2 flex" : "" }'></a>
+  <b :class="open ? 'px-2 flex' : 'py-2 block'"></b>
+</div>
+"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![
+            presets::get_default_regex_pair(),
+            presets::get_alpine_regex_pair(),
+        ]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_heex_class_bindings() {
+    let file_contents = r#"
+<div class="px-2 flex" id={@id}>
+  <.link class={["px-2 flex", @active && "underline block"]} navigate={~p"/"}>Home</.link>
+  <span class={@class}></span>
+  <p class={"py-2 block"}></p>
+  <i class={"px-2 #{@color} flex"}></i>
+</div>
+"#;
+
+    let expected_outcome = r#"
+<div class="flex px-2" id={@id}>
+  <.link class={["flex px-2", @active && "block underline"]} navigate={~p"/"}>Home</.link>
+  <span class={@class}></span>
+  <p class={"block py-2"}></p>
+  <i class={"px-2 #{@color} flex"}></i>
+</div>
+"#;
+
+    let options = default_options_for_test();
+
+    for path in ["index.html.heex", "index.html.eex"] {
+        let regex = options.regex_for_path(Path::new(path));
+
+        assert_eq!(
+            utils::sort_file_contents_with_regex(file_contents, regex, &options).0,
+            expected_outcome
+        );
+    }
+}
+
+#[test]
+fn test_sort_json_field_keeps_the_formatting() {
+    let file_contents = r#"{
+  "button": {
+    "classes": "px-2 flex",   "label": "px-2 flex",
+    "variants": [{ "classes": ["py-2 block", 3] }]
+  },
+  "classes": "px-2 \u0066lex"
+}
+"#;
+
+    let expected_outcome = r#"{
+  "button": {
+    "classes": "flex px-2",   "label": "px-2 flex",
+    "variants": [{ "classes": ["block py-2", 3] }]
+  },
+  "classes": "flex px-2"
+}
+"#;
+
+    let options = default_options_for_test();
+
+    let (sorted_contents, changes) = json::sort_json_field(file_contents, "classes", &options);
+    assert_eq!(sorted_contents, expected_outcome);
+    assert_eq!(changes.len(), 3);
+
+    let (sorted_contents, _) = json::sort_json_field(file_contents, "button.*", &options);
+    assert!(sorted_contents.contains(r#""classes": "flex px-2",   "label": "flex px-2","#));
+    assert!(sorted_contents.contains(r#"["py-2 block", 3]"#));
+
+    // not valid json, the trailing comma
+    let invalid = r#"{ "classes": "px-2 flex", }"#;
+    assert!(!json::has_json_field(invalid, "classes"));
+    assert_eq!(
+        json::sort_json_field(invalid, "classes", &options).0,
+        Cow::Borrowed(invalid)
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_maud_class_chains() {
+    let file_contents = r#"
+html! {
+    div#main.px-2.flex {
+        .py-2.block[active] { "text" }
+        a.underline."hover:flex".mt-2 href="/" { "link" }
+        input.border.px-2 type="text";
+        p class="px-2 flex" { (items.len()) }
+    }
+}
+let count = self.items.len();
+"#;
+
+    let expected_outcome = r#"
+html! {
+    div#main.flex.px-2 {
+        .block[active].py-2 { "text" }
+        a.mt-2.underline."hover:flex" href="/" { "link" }
+        input.px-2.border type="text";
+        p class="flex px-2" { (items.len()) }
+    }
+}
+let count = self.items.len();
+"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![
+            presets::get_default_regex_pair(),
+            presets::get_maud_regex_pair(),
+        ]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_maud_leaves_rust_code_untouched() {
+    let file_contents = r#"
+fn render(&self, config: &Config) -> Markup {
+    if config.hidden.block {
+        return html! {};
+    }
+
+    match self.state.kind {
+        Kind::Card => self.items.iter().count(),
+    };
+
+    html! {
+        @if self.state.kind {
+            div.px-2.flex { (self.items.iter().count()) }
+        }
+        @let total = self.order.total;
+        p { "{" } span.px-2.block { (total) }
+    }
+}
+"#;
+
+    let expected_outcome = r#"
+fn render(&self, config: &Config) -> Markup {
+    if config.hidden.block {
+        return html! {};
+    }
+
+    match self.state.kind {
+        Kind::Card => self.items.iter().count(),
+    };
+
+    html! {
+        @if self.state.kind {
+            div.flex.px-2 { (self.items.iter().count()) }
+        }
+        @let total = self.order.total;
+        p { "{" } span.block.px-2 { (total) }
+    }
+}
+"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![
+            presets::get_default_regex_pair(),
+            presets::get_maud_regex_pair(),
+        ]),
+        custom_class_order: CustomClassOrder::Alphabetical,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_handlebars_and_jinja_interpolations() {
+    let file_contents = r#"
+<a class="{{ extra }} px-2 flex"></a>
+<a class="px-2 flex {{ extra }} underline block"></a>
+<a class='px-2 flex {{ extra "x" }}'></a>
+<a class="px-2 bg-{{ color }}-500 flex"></a>
+<a class="px-2 flex {% if active %}underline block{% endif %} mt-2 inline"></a>
+<a class="px-2 flex {{#if active}}underline block{{/if}}"></a>
+"#;
+
+    let expected_outcome = r#"
+<a class="{{ extra }} flex px-2"></a>
+<a class="flex px-2 {{ extra }} block underline"></a>
+<a class='flex px-2 {{ extra "x" }}'></a>
+<a class="px-2 bg-{{ color }}-500 flex"></a>
+<a class="flex px-2 {% if active %}block underline{% endif %} inline mt-2"></a>
+<a class="flex px-2 {{#if active}}underline block{{/if}}"></a>
+"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![
+            presets::get_default_regex_pair(),
+            presets::get_handlebars_regex_pair(),
+        ]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_extended_sort_order() {
+    let sort_order = serde_json::from_str(
+        r#"{ "prepend": ["custom-first", "px-2"], "append": ["flex", "custom-last"] }"#,
+    )
+    .unwrap();
+
+    let options = Options {
+        sorter: get_sorter_from_config(Some(sort_order), false, TailwindVersion::V3),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(
+            r#"<div class="custom-last flex block px-2 py-2 custom-first other"></div>"#,
+            &options
+        ),
+        r#"<div class="custom-first px-2 block py-2 flex custom-last other"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_order_lists_the_classes_of_the_sorter_in_use() {
+    let default_options = default_options_for_test();
+    let default_order = default_options.sort_order();
+    assert_eq!(default_order.len(), crate::defaults::SORTER.len());
+    assert_eq!(default_order[0], "container");
+
+    let sort_order =
+        serde_json::from_str(r#"{ "prepend": ["custom-first", "container"] }"#).unwrap();
+    let options = Options {
+        sorter: get_sorter_from_config(Some(sort_order), false, TailwindVersion::V3),
+        ..default_options_for_test()
+    };
+
+    let sort_order = options.sort_order();
+    assert_eq!(sort_order[..2], ["custom-first", "container"]);
+    assert_eq!(sort_order.len(), default_order.len() + 1);
+    assert_eq!(sort_order[2..], default_order[1..]);
+}
+
+#[test]
+fn test_sort_file_contents_with_negative_utilities_missing_from_the_sorter() {
+    let sort_order: SortOrder =
+        serde_json::from_str(r#"["inset-1", "flex", "mt-2", "px-2", "translate-x-1/2", "z-10"]"#)
+            .unwrap();
+
+    let options = Options {
+        sorter: get_sorter_from_config(Some(sort_order), false, TailwindVersion::V3),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(
+            r#"<div class="-translate-x-1/2 px-2 -mt-2 flex mt-2 -inset-1 translate-x-1/2 other"></div>"#,
+            &options
+        ),
+        r#"<div class="-inset-1 flex mt-2 -mt-2 px-2 translate-x-1/2 -translate-x-1/2 other"></div>"#
+    );
+
+    assert_eq!(
+        utils::sort_file_contents(
+            r#"<div class="md:-mt-2 md:px-2 md:mt-2 hover:-translate-x-1/2 hover:flex !-mt-2"></div>"#,
+            &options
+        ),
+        r#"<div class="!-mt-2 md:mt-2 md:-mt-2 md:px-2 hover:flex hover:-translate-x-1/2"></div>"#
+    );
+
+    // -z-10 and -order-1 are not in the default sorter, unlike z-10 and order-1
+    assert_eq!(
+        utils::sort_file_contents(
+            r#"<div class="-z-10 flex z-10 -order-1 order-1"></div>"#,
+            &default_options_for_test()
+        ),
+        r#"<div class="flex z-10 -z-10 order-1 -order-1"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_order_array_replaces_the_default_sort_order() {
+    let sort_order: SortOrder = serde_json::from_str(r#"["px-2", "flex"]"#).unwrap();
+    assert_eq!(
+        sort_order,
+        SortOrder::Replace(vec!["px-2".to_string(), "flex".to_string()])
+    );
+
+    let options = Options {
+        sorter: get_sorter_from_config(Some(sort_order), false, TailwindVersion::V3),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(r#"<div class="block flex px-2"></div>"#, &options),
+        r#"<div class="px-2 flex block"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_order_array_extends_the_default_sort_order() {
+    let config = parse_config_file(
+        "rustywind.json",
+        r#"{ "sortOrder": ["px-2", "flex"], "extendDefault": true }"#,
+    )
+    .unwrap();
+
+    let options = Options {
+        sorter: get_sorter_from_config(
+            config.sort_order,
+            config.extend_default.unwrap(),
+            TailwindVersion::V3,
+        ),
+        ..default_options_for_test()
+    };
+
+    // the listed classes come first, the other tailwind classes keep their default order
+    assert_eq!(
+        utils::sort_file_contents(
+            r#"<div class="custom py-2 flex block container px-2"></div>"#,
+            &options
+        ),
+        r#"<div class="px-2 flex container block py-2 custom"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_order_file_has_one_class_per_line() {
+    let sort_order = parse_sort_order_file("px-2\r\n\n  flex\ncustom\n");
+    assert_eq!(sort_order, vec!["px-2", "flex", "custom"]);
+
+    let options = Options {
+        sorter: get_sorter_from_config(
+            Some(SortOrder::Replace(sort_order)),
+            false,
+            TailwindVersion::V3,
+        ),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(r#"<div class="custom flex px-2"></div>"#, &options),
+        r#"<div class="px-2 flex custom"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_removes_duplicated_variant_classes() {
+    let file_contents =
+        r#"<div class="hover:px-2 flex hover:px-2 md:hover:flex md:hover:flex"></div>"#;
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        r#"<div class="flex md:hover:flex hover:px-2"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_removes_duplicates_separated_by_any_whitespace() {
+    let file_contents =
+        "<div class=\"px-2\tflex\n  px-2\u{a0}w-[10px]\u{2003}w-[10px]\r\nflex\"></div>";
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        r#"<div class="flex px-2 w-[10px]"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_normalizes_whitespace() {
+    let file_contents = "<a class=\"  px-2\t\tflex  mt-4 \"></a><b class=\"flex\tpx-2\"></b><i class='flex px-2'></i>";
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        "<a class=\"flex px-2 mt-4\"></a><b class=\"flex px-2\"></b><i class='flex px-2'></i>"
+    );
+
+    let options = Options {
+        preserve_whitespace: true,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        "<a class=\"  flex\t\tpx-2  mt-4 \"></a><b class=\"flex\tpx-2\"></b><i class='flex px-2'></i>"
+    );
+}
+
+#[test]
+fn test_sort_file_contents_only_replaces_the_captured_classes() {
+    let options = Options {
+        regex: FinderRegex::CustomRegex(regex::Regex::new(r"flex=(flex [^;]*);").unwrap()),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents("flex=flex  block;", &options),
+        "flex=block flex;"
+    );
+}
+
+#[test]
+fn test_sort_file_contents_preserving_whitespace() {
+    let file_contents = r#"
+    <div
+      class="
+        px-2 flex
+        underline block
+        px-2   mt-4
+      "
+    ></div>
+    "#;
+
+    let expected_outcome = r#"
+    <div
+      class="
+        block flex
+        px-2 mt-4
+        underline
+      "
+    ></div>
+    "#;
+
+    let options = Options {
+        preserve_whitespace: true,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_arbitrary_values() {
+    let file_contents = r#"<div class="w-[calc(100%-1rem)] grid-cols-[1fr_2fr] bg-[url('a b.png')] flex block"></div>
+<div class='bg-[url("a b.png")] px-2 [&>*]:p-2 flex'></div>"#;
+
+    let expected_outcome = r#"<div class="block flex w-[calc(100%-1rem)] grid-cols-[1fr_2fr] bg-[url('a b.png')]"></div>
+<div class='flex px-2 bg-[url("a b.png")] [&>*]:p-2'></div>"#;
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_duplicated_arbitrary_values_with_spaces() {
+    let file_contents = r#"<div class="bg-[url('a b.png')] flex bg-[url('a b.png')]"></div>"#;
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        r#"<div class="flex bg-[url('a b.png')]"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_max_line_length() {
+    let file_contents = r#"<div>
+    <div class="underline px-4 py-2 flex items-center rounded-lg bg-white shadow-md"></div>
+    <a class="px-2 flex"></a>
+</div>"#;
+
+    let expected_outcome = r#"<div>
+    <div class="flex items-center py-2 px-4
+         underline bg-white rounded-lg shadow-md"></div>
+    <a class="flex px-2"></a>
+</div>"#;
+
+    let options = Options {
+        max_line_length: Some(50),
+        ..default_options_for_test()
+    };
+
+    let sorted_contents = utils::sort_file_contents(file_contents, &options);
+    assert_eq!(sorted_contents, expected_outcome);
+
+    // the wrapped classes are already sorted on the next run
+    assert_eq!(
+        utils::sort_file_contents(&sorted_contents, &options),
+        Cow::Borrowed(expected_outcome)
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_max_line_length_and_tabs() {
+    let file_contents =
+        "<div>\n\t<div class=\"underline px-4 py-2 flex items-center rounded-lg\"></div>\n</div>";
+
+    let options = Options {
+        max_line_length: Some(50),
+        ..default_options_for_test()
+    };
+
+    // the tab is 4 columns wide and the wrapped line reuses the tab of the line
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        "<div>\n\t<div class=\"flex items-center py-2 px-4\n\t     underline rounded-lg\"></div>\n</div>"
+    );
+
+    let options = Options {
+        tab_width: 2,
+        use_tabs: true,
+        ..options
+    };
+
+    // the 2 columns of the tab and the 5 of `<div ` are 3 tabs and a space
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        "<div>\n\t<div class=\"flex items-center py-2 px-4\n\t\t\t underline rounded-lg\"></div>\n</div>"
+    );
+}
+
+#[test]
+fn test_sort_file_contents_keeps_crlf_line_endings() {
+    let file_contents = "<div>\r\n    <div class=\"underline px-4 py-2 flex items-center rounded-lg bg-white shadow-md\"></div>\r\n    <a class=\"px-2\r\n      flex\"></a>\r\n  <p>text</p>\r\n</div>\r\n";
+
+    let expected_outcome = "<div>\r\n    <div class=\"flex items-center py-2 px-4\r\n         underline bg-white rounded-lg shadow-md\"></div>\r\n    <a class=\"flex px-2\"></a>\r\n  <p>text</p>\r\n</div>\r\n";
+
+    let options = Options {
+        max_line_length: Some(50),
+        ..default_options_for_test()
+    };
+
+    let sorted_contents = utils::sort_file_contents(file_contents, &options);
+    assert_eq!(sorted_contents, expected_outcome);
+
+    // the lines without classes are the same bytes, every line still ends with \r\n
+    let lines = |contents: &str| contents.split('\n').map(str::to_string).collect::<Vec<_>>();
+    assert_eq!(lines(&sorted_contents)[0], "<div>\r");
+    assert_eq!(lines(&sorted_contents)[4..], lines(file_contents)[4..]);
+    assert!(!sorted_contents.replace("\r\n", "").contains('\n'));
+
+    // the group separator is written with the line ending of the file too
+    let options = Options {
+        group_prefixes: vec!["bg-".to_string()],
+        group_separator: "\n".to_string(),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents("<a class=\"bg-white px-2\"></a>\r\n", &options),
+        "<a class=\"px-2\r\nbg-white\"></a>\r\n"
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_pinned_marker_classes() {
+    let file_contents = r#"<div class="px-2 peer flex group/item relative group"></div>"#;
+
+    // without the option, group and peer are custom classes sorted last
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        r#"<div class="flex relative px-2 peer group/item group"></div>"#
+    );
+
+    let options = Options {
+        pin_marker_classes: true,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="peer group/item group flex relative px-2"></div>"#
+    );
+
+    // the variants of group and peer are not marker classes
+    assert_eq!(
+        utils::sort_file_contents(
+            r#"<div class="group-hover:flex container px-2 peer-focus:block group"></div>"#,
+            &options
+        ),
+        r#"<div class="container group px-2 group-hover:flex peer-focus:block"></div>"#
+    );
+
+    // before the ignored classes placed first too
+    let options = Options {
+        ignored_classes: vec!["my-card".to_string()],
+        ..options
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(r#"<div class="px-2 my-card flex peer"></div>"#, &options),
+        r#"<div class="peer my-card flex px-2"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_group_prefixes() {
+    let file_contents = r#"<div class="text-sm bg-white px-2 underline flex bg-opacity-50 hover:bg-red-500"></div>"#;
+
+    let expected_outcome = r#"<div class="flex px-2  text-sm underline  bg-white bg-opacity-50  hover:bg-red-500"></div>"#;
+
+    let options = Options {
+        group_prefixes: vec!["bg-".to_string(), "text-".to_string(), "hover:".to_string()],
+        ..default_options_for_test()
+    };
+
+    let sorted_contents = utils::sort_file_contents(file_contents, &options);
+    assert_eq!(sorted_contents, expected_outcome);
+
+    // the separators are whitespace, so the grouped classes are already sorted on the next run
+    assert_eq!(
+        utils::sort_file_contents(&sorted_contents, &options),
+        Cow::Borrowed(expected_outcome)
+    );
+
+    let options = Options {
+        group_separator: "\n".to_string(),
+        ..options
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        "<div class=\"flex px-2\ntext-sm underline\nbg-white bg-opacity-50\nhover:bg-red-500\"></div>"
+    );
+}
+
+#[test]
+fn test_sort_file_contents_ignoring_case() {
+    let file_contents = r#"<div Class="px-2 flex"></div>
+<div CLASS='px-2 Flex block'></div>"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegex(RE_IGNORE_CASE.clone()),
+        ..default_options_for_test()
+    };
+
+    // the classes are still sorted case-sensitively, `Flex` is a custom class
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div Class="flex px-2"></div>
+<div CLASS='block px-2 Flex'></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_custom_classes_first() {
+    let file_contents = r#"<div class="zebra px-2 hover:flex apple flex"></div>"#;
+
+    let options = Options {
+        custom_class_position: CustomClassPosition::First,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="zebra apple flex px-2 hover:flex"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_comma_separated_classes() {
+    let file_contents = r#"
+const classes = ["px-2", 'flex', "grid-cols-[1fr,2fr]",
+  "px-2", "block"];
+const joined = [px-2,flex];
+const strings = ["px-2 flex", "block"];
+const spaced = [px-2 flex];
+"#;
+
+    let regex = FinderRegex::CustomRegex(regex::Regex::new(r"(?s)= \[(.*?)\];").unwrap());
+
+    let options = Options {
+        regex,
+        class_separator: ClassSeparator::Comma,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"
+const classes = ["block", 'flex', "px-2",
+  "grid-cols-[1fr,2fr]"];
+const joined = [flex,px-2];
+const strings = ["px-2 flex", "block"];
+const spaced = [flex px-2];
+"#
+    );
+
+    // the commas are part of the classes by default
+    let options = Options {
+        class_separator: ClassSeparator::Whitespace,
+        ..options
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(r#"= ["px-2", "flex"]; = [px-2,flex];"#, &options),
+        r#"= ["px-2", "flex"]; = [px-2,flex];"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_join_separator() {
+    let regex = FinderRegex::CustomRegex(regex::Regex::new(r"(?s)= \[(.*?)\];").unwrap());
+
+    let options = Options {
+        regex,
+        class_separator: ClassSeparator::Comma,
+        join_separator: ", ".to_string(),
+        ..default_options_for_test()
+    };
+
+    let sorted_contents = utils::sort_file_contents(r#"= [ "px-2","block",flex ];"#, &options);
+    assert_eq!(sorted_contents, r#"= [ "block", flex, "px-2" ];"#);
+
+    // the joined classes are split by the comma again, so they are already sorted
+    assert_eq!(
+        utils::sort_file_contents(&sorted_contents, &options),
+        Cow::Borrowed(r#"= [ "block", flex, "px-2" ];"#)
+    );
+
+    // a whitespace separated list is joined with a single space, a comma would break the attribute
+    assert_eq!(
+        utils::sort_file_contents("= [px-2 block flex];", &options),
+        "= [block flex px-2];"
+    );
+
+    let options = Options {
+        regex: FinderRegex::DefaultRegex,
+        ..options
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(r#"<div class="px-2 flex"></div>"#, &options),
+        r#"<div class="flex px-2"></div>"#
+    );
+
+    // without a join separator, the comma separated list keeps its original separators
+    let options = Options {
+        regex: FinderRegex::CustomRegex(regex::Regex::new(r"(?s)= \[(.*?)\];").unwrap()),
+        class_separator: ClassSeparator::Comma,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(r#"= ["px-2",  "flex"];"#, &options),
+        r#"= ["flex",  "px-2"];"#
+    );
+
+    let options = Options {
+        join_separator: "\n".to_string(),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(r#"<a class="px-2 flex"></a>"#, &options),
+        "<a class=\"flex\npx-2\"></a>"
+    );
+
+    let join_separator = |args: &[&str]| {
+        let cli = Cli::parse_from([&["rustywind", "."], args].concat());
+        get_join_separator_from_cli(&cli, cli.class_separator.unwrap_or_default())
+            .map_err(|error| error.to_string())
+    };
+
+    assert_eq!(join_separator(&[]), Ok(" ".to_string()));
+    assert_eq!(
+        join_separator(&["--join-separator", ", ", "--class-separator", "comma"]),
+        Ok(", ".to_string())
+    );
+    assert!(join_separator(&["--join-separator", ","]).is_err());
+    assert!(join_separator(&["--join-separator", "|", "--class-separator", "comma"]).is_err());
+}
+
+#[test]
+fn test_sort_file_contents_with_preserved_custom_class_positions() {
+    let file_contents = r#"<div class="zebra px-2 hover:flex apple flex hover:my-card"></div>"#;
+
+    let options = Options {
+        preserve_custom_position: true,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="zebra flex px-2 apple hover:flex hover:my-card"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_ignored_classes() {
+    let file_contents = r#"<div class="px-2 js-toggle flex my-card js-sentinel block"></div>"#;
+
+    let config = parse_config_file(
+        "rustywind.json",
+        r#"{ "ignoreClasses": ["js-sentinel", "js-toggle"] }"#,
+    )
+    .unwrap();
+
+    let options = Options {
+        ignored_classes: config.ignore_classes.unwrap(),
+        ..default_options_for_test()
+    };
+
+    // the ignored classes keep the order they were written in, not the order of the list
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="js-toggle js-sentinel block flex px-2 my-card"></div>"#
+    );
+
+    let options = Options {
+        ignored_class_position: IgnoredClassPosition::Last,
+        ..options
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="block flex px-2 my-card js-toggle js-sentinel"></div>"#
+    );
+
+    let options = Options {
+        ignored_class_position: IgnoredClassPosition::Original,
+        ..options
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="block js-toggle flex px-2 js-sentinel my-card"></div>"#
+    );
+}
+
+#[test]
+fn test_parse_toml_config_file() {
+    let config = parse_config_file(
+        "rustywind.toml",
+        r#"
+classFunctions = ["clsx", "tw"]
+sortCustom = "alphabetical"
+
+[sortOrder]
+prepend = ["custom-first"]
+append = ["custom-last"]
+
+[[customRegex]]
+container = "tw\\(.*?\\)"
+class = '"([^"]*)"'
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.sort_order,
+        Some(SortOrder::Extend {
+            prepend: vec!["custom-first".to_string()],
+            append: vec!["custom-last".to_string()],
+        })
+    );
+    assert_eq!(config.sort_custom, Some(CustomClassOrder::Alphabetical));
+    assert_eq!(
+        config.class_functions,
+        Some(vec!["clsx".to_string(), "tw".to_string()])
+    );
+
+    let custom_regex = config.custom_regex.unwrap();
+    assert_eq!(custom_regex[0].container, r"tw\(.*?\)");
+    assert_eq!(custom_regex[0].class, r#""([^"]*)""#);
+
+    let config = parse_config_file("rustywind.toml", r#"sortOrder = ["px-2", "flex"]"#).unwrap();
+    assert_eq!(
+        config.sort_order,
+        Some(SortOrder::Replace(vec![
+            "px-2".to_string(),
+            "flex".to_string()
+        ]))
+    );
+
+    assert!(parse_config_file("rustywind.json", r#"sortOrder = ["px-2"]"#).is_err());
+    assert!(parse_config_file("rustywind.toml", r#"{ "sortOrder": ["px-2"] }"#).is_err());
+}
+
+#[test]
+fn test_merge_config_files() {
+    let base = parse_config_file(
+        "base.json",
+        r#"{
+            "sortOrder": ["px-2", "flex"],
+            "sortCustom": "alphabetical",
+            "customRegex": [{ "container": "a", "class": "b" }],
+            "fileRegex": { "*.rs": [], "*.vue": [] }
+        }"#,
+    )
+    .unwrap();
+
+    let project = parse_config_file(
+        "project.toml",
+        r#"
+sortOrder = ["flex"]
+fileRegex = { "*.rs" = [{ container = "e", class = "f" }] }
+
+[[customRegex]]
+container = "c"
+class = "d"
+"#,
+    )
+    .unwrap();
+
+    let config = base.merge(project);
+
+    assert_eq!(
+        config.sort_order,
+        Some(SortOrder::Replace(vec!["flex".to_string()]))
+    );
+    assert_eq!(config.sort_custom, Some(CustomClassOrder::Alphabetical));
+
+    let custom_regex = config.custom_regex.unwrap();
+    let containers: Vec<&str> = custom_regex
+        .iter()
+        .map(|entry| entry.container.as_str())
+        .collect();
+    assert_eq!(containers, vec!["a", "c"]);
+
+    let file_regex = config.file_regex.unwrap();
+    assert_eq!(file_regex["*.rs"][0].container, "e");
+    assert!(file_regex["*.vue"].is_empty());
+}
+
+#[test]
+fn test_find_config_file_in_parent_directories() {
+    let dir = TestDir::new("config");
+    let nested = dir.join("nested").join("deeper");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(nested.join("index.html"), "").unwrap();
+    fs::write(dir.join("rustywind.toml"), "").unwrap();
+
+    let found_from_file = find_config_file(&nested.join("index.html"));
+    let found_from_dir = find_config_file(&nested);
+
+    fs::write(dir.join("nested").join("rustywind.json"), "").unwrap();
+    let found_closest = find_config_file(&nested);
+
+    let dir = dir.canonicalize().unwrap();
+
+    assert_eq!(found_from_file, Some(dir.join("rustywind.toml")));
+    assert_eq!(found_from_dir, Some(dir.join("rustywind.toml")));
+    assert_eq!(
+        found_closest,
+        Some(dir.join("nested").join("rustywind.json"))
+    );
+}
+
+#[test]
+fn test_config_in_package_json() {
+    let dir = TestDir::new("package");
+    let nested = dir.join("nested");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(dir.join("package.json"), r#"{ "name": "app" }"#).unwrap();
+
+    let without_key = find_config_file(&nested);
+
+    fs::write(
+        dir.join("package.json"),
+        r#"{ "name": "app", "rustywind": { "sortOrder": ["px-2", "flex"] } }"#,
+    )
+    .unwrap();
+    let with_key = find_config_file(&nested);
+
+    fs::write(dir.join("rustywind.toml"), "").unwrap();
+    let dedicated_file = find_config_file(&nested);
+
+    let dir = dir.canonicalize().unwrap();
+
+    assert_eq!(without_key, None);
+    assert_eq!(with_key, Some(dir.join("package.json")));
+    assert_eq!(dedicated_file, Some(dir.join("rustywind.toml")));
+
+    let config = parse_config_file(
+        "app/package.json",
+        r#"{ "name": "app", "rustywind": { "sortOrder": ["px-2", "flex"] } }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.sort_order,
+        Some(SortOrder::Replace(vec![
+            "px-2".to_string(),
+            "flex".to_string()
+        ]))
+    );
+
+    assert!(parse_config_file("package.json", r#"{ "name": "app" }"#).is_err());
+}
+
+#[test]
+fn test_sort_file_contents_with_file_regex() {
+    let config = parse_config_file(
+        "rustywind.json",
+        r#"{ "fileRegex": { "*.rs": [{ "container": "class=\\(\"[^\"]*\"", "class": "\"([^\"]*)\"" }] } }"#,
+    )
+    .unwrap();
+
+    let options = Options {
+        file_regex: get_file_regex_from_config(&config).unwrap(),
+        ..default_options_for_test()
+    };
+
+    let file_contents = r#"view! { <div class=("px-2 flex", active) title="px-2 flex"></div> }"#;
+
+    let rust_regex = options.regex_for_path(Path::new("src/app.rs"));
+    assert_eq!(
+        utils::sort_file_contents_with_regex(file_contents, rust_regex, &options).0,
+        r#"view! { <div class=("flex px-2", active) title="px-2 flex"></div> }"#
+    );
+
+    let html_regex = options.regex_for_path(Path::new("index.html"));
+    assert!(matches!(html_regex, FinderRegex::DefaultRegex));
+    assert!(!utils::has_classes_with_regex(file_contents, html_regex));
+}
+
+#[test]
+fn test_regex_for_path_reuses_the_compiled_finders() {
+    let compilations = || FINDER_COMPILATIONS.with(Cell::get);
+    let config = parse_config_file(
+        "rustywind.json",
+        r#"{ "fileRegex": { "*.rs": [{ "container": "class=\\(\"[^\"]*\"", "class": "\"([^\"]*)\"" }] } }"#,
+    )
+    .unwrap();
+
+    let before = compilations();
+    let options = Options {
+        file_regex: get_file_regex_from_config(&config).unwrap(),
+        ..default_options_for_test()
+    };
+    assert_eq!(compilations(), before + 1);
+
+    // the finder of an extension is compiled at most once, on first use, and every other file of
+    // the same extension reuses it
+    for extension in ["rs", "vue", "tsx", "css", "html"] {
+        let first = options.regex_for_path(Path::new(&format!("first.{extension}")));
+        let after_first = compilations();
+
+        for index in 0..10 {
+            let path = format!("src/file-{index}.{extension}");
+            assert!(std::ptr::eq(
+                first,
+                options.regex_for_path(Path::new(&path))
+            ));
+        }
+
+        assert_eq!(compilations(), after_first, "{extension}");
+    }
+
+    // sorting the files doesn't compile the finders again either
+    let after_lookups = compilations();
+    for index in 0..10 {
+        let path = format!("src/file-{index}.rs");
+        let (sorted, _) = options.sort_contents(Path::new(&path), r#"<div class=("px-2 flex")>"#);
+        assert_eq!(sorted, r#"<div class=("flex px-2")>"#);
+    }
+    assert_eq!(compilations(), after_lookups);
+
+    assert!(std::ptr::eq(
+        options.regex_for_path(Path::new("src/app.rs")),
+        &options.file_regex[0].regex
+    ));
+}
+
+#[test]
+fn test_class_changes_count_removed_duplicates() {
+    let (_, changes) = utils::sort_file_contents_with_changes(
+        r#"<div class="px-2 flex px-2 bg-[url('a b.png')] bg-[url('a b.png')]"></div><a class="px-2 flex">"#,
+        &default_options_for_test(),
+    );
+
+    let removed_duplicates: Vec<usize> = changes
+        .iter()
+        .map(ClassChange::removed_duplicates)
+        .collect();
+    assert_eq!(removed_duplicates, vec![2, 0]);
+}
+
+#[test]
+fn test_sort_file_contents_collapses_exact_variant_duplicates() {
+    let file_contents = r#"<input class="focus:ring px-2 focus:ring focus:ring-2 px-2">"#;
+    let (sorted_contents, changes) =
+        utils::sort_file_contents_with_changes(file_contents, &default_options_for_test());
+
+    assert_eq!(
+        sorted_contents,
+        r#"<input class="px-2 focus:ring-2 focus:ring">"#
+    );
+    assert_eq!(changes[0].duplicated_classes(), vec!["focus:ring", "px-2"]);
+
+    let (_, changes) = utils::sort_file_contents_with_changes(
+        r#"<a class="px-2 flex">"#,
+        &default_options_for_test(),
+    );
+    assert!(changes[0].duplicated_classes().is_empty());
+
+    let config = parse_config_file("rustywind.json", r#"{ "warnDuplicates": true }"#).unwrap();
+    assert_eq!(config.warn_duplicates, Some(true));
+}
+
+#[test]
+fn test_sort_file_contents_with_leptos_class_attributes() {
+    let file_contents = r#"
+view! {
+    <div class="px-2 flex" class=("underline block", move || active()) class:px-2=active>
+        <a class = ( "py-2 flex" , is_active )></a>
+    </div>
+}
+"#;
+
+    let expected_outcome = r#"
+view! {
+    <div class="flex px-2" class=("block underline", move || active()) class:px-2=active>
+        <a class = ( "flex py-2" , is_active )></a>
+    </div>
+}
+"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![
+            presets::get_default_regex_pair(),
+            presets::get_leptos_regex_pair(),
+        ]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_cva_variants() {
+    let file_contents = r#"
+const button = cva("px-2 flex rounded", {
+  variants: {
+    size: { sm: "py-1 text-sm", lg: 'py-2 text-lg' },
+    "data-state": { "open now": "underline block" },
+  },
+  compoundVariants: [{ size: "sm", class: "mt-2 block" }],
+  defaultVariants: { size: "sm" },
+});
+const other = fn("px-2 flex");
+"#;
+
+    let expected_outcome = r#"
+const button = cva("flex px-2 rounded", {
+  variants: {
+    size: { sm: "py-1 text-sm", lg: 'py-2 text-lg' },
+    "data-state": { "open now": "block underline" },
+  },
+  compoundVariants: [{ size: "sm", class: "block mt-2" }],
+  defaultVariants: { size: "sm" },
+});
+const other = fn("px-2 flex");
+"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![presets::get_cva_regex_pair()]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_tw_merge_and_clsx() {
+    let file_contents = r#"
+const a = twMerge("px-2 flex", variant, isActive && 'mt-2 block');
+const b = tw(clsx("py-1 inline", size), "underline text-sm");
+const c = merge("px-2 flex");
+"#;
+
+    let expected_outcome = r#"
+const a = twMerge("flex px-2", variant, isActive && 'block mt-2');
+const b = tw(clsx("inline py-1", size), "text-sm underline");
+const c = merge("px-2 flex");
+"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![
+            presets::get_clsx_regex_pair(&CLASS_FUNCTIONS).unwrap(),
+            presets::get_tw_merge_regex_pair(),
+        ]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_mdx_skips_fenced_code_blocks() {
+    let file_contents = r#"
+# Button
+
+<div class="px-2 flex">
+
+```html
+<div class="px-2 flex"></div>
+```
+
+  ~~~jsx
+  <a className="px-2 flex" />
+  ~~~
+
+<Button className="py-2 block" />
+
+```
+<div class="px-2 flex"></div>
+"#;
+
+    let expected_outcome = r#"
+# Button
+
+<div class="flex px-2">
+
+```html
+<div class="px-2 flex"></div>
+```
+
+  ~~~jsx
+  <a className="px-2 flex" />
+  ~~~
+
+<Button className="block py-2" />
+
+```
+<div class="px-2 flex"></div>
+"#;
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![presets::get_mdx_regex_pair()]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        expected_outcome
+    );
+}
+
+#[test]
+fn test_sort_file_contents_only_in_range() {
+    let file_contents = r#"<a class="px-2 flex"></a>
+<b class="px-2 flex"></b>
+<i class="px-2 flex"></i>"#;
+
+    let second_line_start = file_contents.find("<b").unwrap();
+    let second_line_end = file_contents.find("</b>").unwrap();
+
+    let options = Options {
+        range: Some(second_line_start..second_line_end),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<a class="px-2 flex"></a>
+<b class="flex px-2"></b>
+<i class="px-2 flex"></i>"#
+    );
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![presets::get_default_regex_pair()]),
+        range: Some(0..second_line_start + 5),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<a class="flex px-2"></a>
+<b class="px-2 flex"></b>
+<i class="px-2 flex"></i>"#
+    );
+}
+
+#[test]
+fn test_parse_custom_regex_reports_the_failing_entry() {
+    let config = parse_config_file(
+        "rustywind.json",
+        r#"{ "customRegex": [
+            { "container": "tw\\(.*?\\)", "class": "\"([^\"]*)\"" },
+            { "container": "cx(", "class": "\"([^\"]*)\"" }
+        ] }"#,
+    )
+    .unwrap();
+
+    let error = parse_custom_regex(&config.custom_regex.unwrap()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Unable to parse the container regex `cx(` of customRegex entry 2"
+    );
+
+    let config = parse_config_file(
+        "rustywind.json",
+        r#"{ "customRegex": [{ "container": "tw\\(.*?\\)", "class": "\"[^\"]*\"" }] }"#,
+    )
+    .unwrap();
+
+    let error = parse_custom_regex(&config.custom_regex.unwrap()).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        r#"custom regex error, the class regex `"[^"]*"` of customRegex entry 1 requires at-least 2 capture groups"#
+    );
+}
+
+#[test]
+fn test_search_paths_filtered_by_extension() {
+    let dir = TestDir::new("ext");
+
+    for file_name in [
+        "index.html",
+        "app.js",
+        "app.min.js",
+        "admin.js",
+        "Cargo.lock",
+    ] {
+        fs::write(dir.join(file_name), "").unwrap();
+    }
+
+    let extension_filter = ExtensionFilter {
+        include: vec!["html".to_string(), "js".to_string()],
+        exclude: vec!["min.js".to_string()],
+    };
+
+    let mut search_paths = get_search_paths_from_starting_paths(
+        std::slice::from_ref(&dir),
+        &extension_filter,
+        None,
+        &WalkOptions::default(),
+    );
+    search_paths.sort();
+
+    let excluded_only = ExtensionFilter {
+        exclude: vec!["js".to_string()],
+        ..ExtensionFilter::default()
+    };
+
+    let mut search_paths_without_js = get_search_paths_from_starting_paths(
+        std::slice::from_ref(&dir),
+        &excluded_only,
+        None,
+        &WalkOptions::default(),
+    );
+    search_paths_without_js.sort();
+
+    assert_eq!(
+        search_paths,
+        vec![
+            dir.join("admin.js"),
+            dir.join("app.js"),
+            dir.join("index.html")
+        ]
+    );
+    assert_eq!(
+        search_paths_without_js,
+        vec![dir.join("Cargo.lock"), dir.join("index.html")]
+    );
+}
+
+#[test]
+fn test_search_paths_are_sorted() {
+    let dir = TestDir::new("sorted");
+    fs::create_dir_all(dir.join("b")).unwrap();
+    fs::create_dir_all(dir.join("a")).unwrap();
+
+    for file_name in ["z.html", "b/index.html", "a.html", "a/index.html", "m.html"] {
+        fs::write(dir.join(file_name), "").unwrap();
+    }
+
+    let starting_paths = [dir.join("m.html"), dir.clone()];
+    let search_paths = get_search_paths_from_starting_paths(
+        &starting_paths,
+        &ExtensionFilter::default(),
+        None,
+        &WalkOptions::default(),
+    );
+
+    // the parallel walk finds `m.html` twice as well, it has to be deduplicated the same way
+    let parallel_search_paths = get_search_paths_from_starting_paths(
+        &starting_paths,
+        &ExtensionFilter::default(),
+        Some(4),
+        &WalkOptions::default(),
+    );
+    let auto_search_paths = get_search_paths_from_starting_paths(
+        &starting_paths,
+        &ExtensionFilter::default(),
+        Some(0),
+        &WalkOptions::default(),
+    );
+
+    assert_eq!(parallel_search_paths, search_paths);
+    assert_eq!(auto_search_paths, search_paths);
+
+    assert_eq!(
+        search_paths,
+        vec![
+            dir.join("a/index.html"),
+            dir.join("a.html"),
+            dir.join("b/index.html"),
+            dir.join("m.html"),
+            dir.join("z.html"),
+        ]
+    );
+}
+
+#[test]
+fn test_files_matching_globs() {
+    let dir = TestDir::new("files");
+    fs::create_dir_all(dir.join("src/nested")).unwrap();
+
+    for file_name in [
+        "index.html",
+        "src/app.html",
+        "src/nested/page.html",
+        "src/app.jsx",
+    ] {
+        fs::write(dir.join(file_name), "").unwrap();
+    }
+
+    let globs = [
+        format!("{}/src/**/*.html", dir.display()),
+        format!("{}/*.jsx", dir.display()),
+    ];
+
+    let mut files = get_files_matching(&globs, &WalkOptions::default()).unwrap();
+    files.sort();
+    let invalid = get_files_matching(
+        &[format!("{}/src/[", dir.display())],
+        &WalkOptions::default(),
+    );
+
+    assert_eq!(
+        files,
+        vec![dir.join("src/app.html"), dir.join("src/nested/page.html")]
+    );
+    assert!(invalid.is_err());
+}
+
+#[test]
+fn test_search_paths_from_file_list() {
+    let dir = TestDir::new("files-from");
+    fs::create_dir_all(dir.join("src")).unwrap();
+
+    for file_name in ["index.html", "src/app.jsx", "src/app.min.js"] {
+        fs::write(dir.join(file_name), "").unwrap();
+    }
+
+    let file_list = [
+        "src/app.jsx",
+        "  index.html  ",
+        "",
+        "deleted.html",
+        "src/app.min.js",
+        "src",
+    ]
+    .map(|file| {
+        if file.trim().is_empty() {
+            file.to_string()
+        } else {
+            format!("{}/{}", dir.display(), file.trim())
+        }
+    })
+    .join("\n");
+
+    let files = parse_file_list(&file_list);
+    assert_eq!(files.len(), 5);
+
+    let extension_filter = ExtensionFilter {
+        exclude: vec!["min.js".to_string()],
+        ..ExtensionFilter::default()
+    };
+
+    let search_paths = get_search_paths_from_file_list(&files, &extension_filter);
+
+    assert_eq!(
+        search_paths,
+        vec![dir.join("index.html"), dir.join("src/app.jsx")]
+    );
+}
+
+#[test]
+fn test_get_staged_files_skips_unstaged_and_partially_staged_files() {
+    let dir = TestDir::new("git");
+    fs::create_dir_all(dir.join("src")).unwrap();
+
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&*dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    };
+
+    git(&["init", "-q"]);
+    fs::write(dir.join("src/staged.html"), "a").unwrap();
+    fs::write(dir.join("partial.html"), "a").unwrap();
+    fs::write(dir.join("unstaged.html"), "a").unwrap();
+    git(&["add", "src/staged.html", "partial.html"]);
+    fs::write(dir.join("partial.html"), "b").unwrap();
+
+    let staged_files = git::get_staged_files(&dir.join("src"));
+    let root = dir.canonicalize().unwrap();
+
+    assert_eq!(staged_files.unwrap(), vec![root.join("src/staged.html")]);
+    assert!(git::get_staged_files(&std::env::temp_dir().join("rustywind-not-a-dir")).is_err());
+}
+
+#[test]
+fn test_get_changed_files_since_a_git_ref() {
+    let dir = TestDir::new("since");
+    fs::create_dir_all(dir.join("src")).unwrap();
+
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&*dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    };
+
+    git(&["init", "-q", "-b", "main"]);
+    fs::write(dir.join("unchanged.html"), "a").unwrap();
+    fs::write(dir.join("deleted.html"), "a").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "base"]);
+
+    git(&["checkout", "-q", "-b", "feature"]);
+    fs::write(dir.join("src/added.html"), "a").unwrap();
+    fs::remove_file(dir.join("deleted.html")).unwrap();
+    git(&["add", "-A"]);
+    git(&["commit", "-q", "-m", "feature"]);
+    fs::write(dir.join("uncommitted.html"), "a").unwrap();
+
+    let changed_files = git::get_changed_files_since(&dir.join("src"), "main");
+    let unknown_ref = git::get_changed_files_since(&dir, "not-a-branch");
+    let root = dir.canonicalize().unwrap();
+
+    assert_eq!(changed_files.unwrap(), vec![root.join("src/added.html")]);
+    assert!(unknown_ref.is_err());
+}
+
+#[test]
+fn test_cache_is_invalidated_when_the_key_or_contents_change() {
+    let dir = TestDir::new("cache");
+    let cache_path = dir.join(".rustywind-cache");
+    let file = Path::new("index.html");
+
+    let mut cache = Cache::load(&cache_path, 1);
+    assert!(!cache.is_sorted(file, "sorted"));
+
+    cache.insert(file.to_owned(), "sorted");
+    cache.save(&cache_path).unwrap();
+
+    let same_key = Cache::load(&cache_path, 1);
+    let other_key = Cache::load(&cache_path, 2);
+
+    assert!(same_key.is_sorted(file, "sorted"));
+    assert!(!same_key.is_sorted(file, "changed"));
+    assert!(!same_key.is_sorted(Path::new("other.html"), "sorted"));
+    assert!(!other_key.is_sorted(file, "sorted"));
+}
+
+#[test]
+fn test_cache_key_is_derived_from_the_resolved_options() {
+    let default_key = get_cache_key(&default_options_for_test());
+
+    // the options that don't change how the files are sorted keep the cache
+    let options = Options {
+        stats: true,
+        quiet: true,
+        write_mode: WriteMode::ToFile,
+        ..default_options_for_test()
+    };
+    assert_eq!(get_cache_key(&options), default_key);
+
+    let options = Options {
+        pin_marker_classes: true,
+        ..default_options_for_test()
+    };
+    assert_ne!(get_cache_key(&options), default_key);
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![presets::get_maud_regex_pair()]),
+        ..default_options_for_test()
+    };
+    assert_ne!(get_cache_key(&options), default_key);
+
+    // every `HashMap` iterates in another order, the key of the same sort order is the same
+    let sorter_key = |classes: &[&str]| {
+        get_cache_key(&Options {
+            sorter: Sorter::CustomSorter(
+                classes
+                    .iter()
+                    .enumerate()
+                    .map(|(index, class)| (class.to_string(), index))
+                    .collect(),
+            ),
+            ..default_options_for_test()
+        })
+    };
+    let classes = ["flex", "px-2", "py-2", "mt-2", "block", "underline"];
+
+    assert_eq!(sorter_key(&classes), sorter_key(&classes));
+    assert_ne!(sorter_key(&classes), sorter_key(&["px-2", "flex"]));
+    assert_ne!(sorter_key(&classes), default_key);
+}
+
+#[test]
+fn test_cache_is_on_by_default_and_an_already_sorted_file_is_unchanged() {
+    let dir = TestDir::new("cache-default");
+    let path = dir.join("index.html");
+    fs::write(&path, r#"<a class="px-2 flex"></a>"#).unwrap();
+
+    let cache_of = |args: &[&str]| {
+        let cli = Cli::parse_from([&["rustywind"], args, &[dir.to_str().unwrap()]].concat());
+        Options::new_from_cli(cli).unwrap().cache.is_some()
+    };
+
+    assert!(cache_of(&["--check-formatted"]));
+    assert!(cache_of(&["--dry-run"]));
+    assert!(!cache_of(&["--no-cache", "--check-formatted"]));
+    assert!(!cache_of(&["--output-format", "json"]));
+
+    // a file found in the cache is not sorted again, but it's still counted as a file with classes
+    let mut cache = Cache::new(0);
+    cache.insert(path.clone(), r#"<a class="px-2 flex"></a>"#);
+
+    let options = Options {
+        cache: Some(cache),
+        ..default_options_for_test()
+    };
+    let outcome = options.apply(&path);
+
+    assert_eq!(
+        outcome.unwrap(),
+        Outcome::Unchanged {
+            contents: r#"<a class="px-2 flex"></a>"#.to_string()
+        }
+    );
+}
+
+#[test]
+fn test_cache_skips_the_files_written_by_the_previous_run() {
+    let dir = TestDir::new("cache-write");
+    let cache_path = dir.join(".rustywind-cache");
+    let changed = dir.join("changed.html");
+    let unchanged = dir.join("unchanged.html");
+    fs::write(&changed, r#"<a class="px-2 flex"></a>"#).unwrap();
+    fs::write(&unchanged, r#"<a class="flex px-2"></a>"#).unwrap();
+
+    let options = Options {
+        write_mode: WriteMode::ToFile,
+        cache: Some(Cache::load(&cache_path, 1)),
+        ..default_options_for_test()
+    };
+
+    // the first run writes the changed file, both files are recorded
+    for path in [&changed, &unchanged] {
+        if let Outcome::Changed { before, after, .. } = options.apply(path).unwrap() {
+            options.write_sorted_file(path, &after, &before).unwrap();
+        }
+    }
+    options.cache.as_ref().unwrap().save(&cache_path).unwrap();
+
+    let next_run = Cache::load(&cache_path, 1);
+    let changed_contents = fs::read_to_string(&changed).unwrap();
+    let unchanged_contents = fs::read_to_string(&unchanged).unwrap();
+
+    assert_eq!(changed_contents, r#"<a class="flex px-2"></a>"#);
+    assert!(next_run.is_sorted(&changed, &changed_contents));
+    assert!(next_run.is_sorted(&unchanged, &unchanged_contents));
+}
+
+#[test]
+fn test_is_search_path_respects_ignore_files_and_extensions() {
+    let dir = TestDir::new("watch");
+    let nested = dir.join("nested");
+    fs::create_dir_all(&nested).unwrap();
+
+    fs::write(dir.join(".rustywindignore"), "skip.html\n").unwrap();
+    for file_name in ["index.html", "skip.html", "app.min.js"] {
+        fs::write(nested.join(file_name), "").unwrap();
+    }
+
+    let options = Options {
+        extension_filter: ExtensionFilter {
+            exclude: vec!["min.js".to_string()],
+            ..ExtensionFilter::default()
+        },
+        ..default_options_for_test()
+    };
+
+    let is_search_path = |file_name: &str| options.is_search_path(&nested.join(file_name));
+    let results = [
+        is_search_path("index.html"),
+        is_search_path("skip.html"),
+        is_search_path("app.min.js"),
+        is_search_path("missing.html"),
+    ];
+
+    assert_eq!(results, [true, false, false, false]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_fix_only_resolves_symlinks() {
+    let dir = TestDir::new("fix-only");
+    let allowed = dir.join("allowed");
+    let sibling = dir.join("sibling");
+    fs::create_dir_all(&allowed).unwrap();
+    fs::create_dir_all(&sibling).unwrap();
+
+    fs::write(allowed.join("index.html"), "").unwrap();
+    fs::write(sibling.join("index.html"), "").unwrap();
+    std::os::unix::fs::symlink(sibling.join("index.html"), allowed.join("link.html")).unwrap();
+
+    let options = Options {
+        fix_only: vec![allowed.canonicalize().unwrap()],
+        ..default_options_for_test()
+    };
+
+    let results = [
+        options.check_fix_only(&allowed.join("index.html")).is_ok(),
+        options.check_fix_only(&allowed.join("new.html")).is_ok(),
+        options.check_fix_only(&allowed.join("link.html")).is_ok(),
+        options.check_fix_only(&sibling.join("index.html")).is_ok(),
+        options
+            .check_fix_only(&allowed.join("..").join("sibling").join("new.html"))
+            .is_ok(),
+        default_options_for_test()
+            .check_fix_only(&sibling.join("index.html"))
+            .is_ok(),
+    ];
+
+    assert_eq!(results, [true, true, false, false, false, true]);
+}
+
+#[test]
+fn test_find_unmatched_class_attributes() {
+    let file_contents = r#"<div class="px-2 flex">
+<a class="" className=''>
+<i :class="{ 'px-2': active }" x-bind:class="open">
+<span class="px-2 flex
+  <p className='block {{ extra }}'></p>
+</span>
+"#;
+
+    assert_eq!(
+        utils::find_unmatched_class_attributes(file_contents, &FinderRegex::DefaultRegex),
+        vec![4, 5]
+    );
+
+    let options = default_options_for_test();
+    let vue_regex = options.regex_for_path(Path::new("app.vue"));
+    assert_eq!(
+        utils::find_unmatched_class_attributes(r#"<i :class="{ a: b }" class="flex">"#, vue_regex),
+        Vec::<usize>::new()
+    );
+}
+
+#[test]
+fn test_sort_file_contents_borrows_already_sorted_contents() {
+    let options = default_options_for_test();
+    let sorted = r#"<div class="flex px-2"></div><a className='block'></a>"#;
+
+    assert!(matches!(
+        utils::sort_file_contents(sorted, &options),
+        Cow::Borrowed(_)
+    ));
+
+    assert!(matches!(
+        utils::sort_file_contents(r#"<div class="px-2 flex"></div>"#, &options),
+        Cow::Owned(_)
+    ));
+}
+
+#[test]
+fn test_sort_file_contents_with_css_apply_directives() {
+    let file_contents = r#"
+.btn {
+  @apply px-2 flex py-1;
+}
+
+.card { @apply underline   block  ; color: red; }
+.old { @apply px-2 flex !important; }
+.important { @apply !px-2 flex; }
+"#;
+
+    let expected_outcome = r#"
+.btn {
+  @apply flex py-1 px-2;
+}
+
+.card { @apply block underline  ; color: red; }
+.old { @apply flex px-2 !important; }
+.important { @apply flex !px-2; }
+"#;
+
+    let options = default_options_for_test();
+
+    for path in ["styles.css", "styles.scss"] {
+        let regex = options.regex_for_path(Path::new(path));
+
+        assert_eq!(
+            utils::sort_file_contents_with_regex(file_contents, regex, &options).0,
+            expected_outcome
+        );
+    }
+}
+
+#[test]
+fn test_sort_file_contents_with_tsx_template_literals() {
+    let file_contents = r#"
+<div className={`px-2 flex ${active ? 'px-4 block' : ''} underline mt-2 ${size} bg-${color}-500 p-1`}>
+<a className = { `mt-2 flex
+    ${fn({ a: 1 })} px-2 block` }>
+<span className={`flex`} class="px-2 flex">
+"#;
+
+    let expected_outcome = r#"
+<div className={`flex px-2 ${active ? 'px-4 block' : ''} mt-2 underline ${size} bg-${color}-500 p-1`}>
+<a className = { `flex mt-2
+    ${fn({ a: 1 })} block px-2` }>
+<span className={`flex`} class="flex px-2">
+"#;
+
+    let options = default_options_for_test();
+
+    for path in ["app.tsx", "app.jsx"] {
+        let regex = options.regex_for_path(Path::new(path));
+
+        assert_eq!(
+            utils::sort_file_contents_with_regex(file_contents, regex, &options).0,
+            expected_outcome
+        );
+    }
+
+    let options = Options {
+        regex: FinderRegex::CustomRegexEntries(vec![presets::get_tsx_regex_pair()]),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(r#"<b className={`px-2 flex ${a}`}>"#, &options),
+        r#"<b className={`flex px-2 ${a}`}>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_jsx_string_concatenations() {
+    let file_contents = r#"
+<div className={"px-2 flex " + base}>
+<a className={'px-2 flex' + " " + cn(a, b) + ' underline mt-2 '}>
+<b className = { styles.card + " py-2 block" } class="px-2 flex">
+"#;
+
+    // each string is sorted on its own and keeps the whitespace that separates it from the others
+    let expected_outcome = r#"
+<div className={"flex px-2 " + base}>
+<a className={'flex px-2' + " " + cn(a, b) + ' mt-2 underline '}>
+<b className = { styles.card + " block py-2" } class="flex px-2">
+"#;
+
+    let options = default_options_for_test();
+
+    for path in ["app.tsx", "app.jsx"] {
+        let regex = options.regex_for_path(Path::new(path));
+
+        assert_eq!(
+            utils::sort_file_contents_with_regex(file_contents, regex, &options).0,
+            expected_outcome
+        );
+    }
+}
+
+#[test]
+fn test_sort_file_contents_with_custom_variant_order() {
+    let file_contents = r#"<div class="hover:flex md:flex dark:flex flex focus:flex"></div>"#;
+
+    let options = Options {
+        variant_order: vec!["focus".to_string(), "md".to_string(), "unknown".to_string()],
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="flex focus:flex md:flex dark:flex hover:flex"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_sorts_breakpoints_mobile_first() {
+    let file_contents =
+        r#"<div class="lg:flex 2xl:flex md:flex hover:flex sm:flex xl:flex"></div>"#;
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        r#"<div class="sm:flex md:flex lg:flex xl:flex 2xl:flex hover:flex"></div>"#
+    );
+
+    let options = Options {
+        variant_order: vec!["hover".to_string(), "xl".to_string(), "sm".to_string()],
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="hover:flex sm:flex md:flex lg:flex xl:flex 2xl:flex"></div>"#
+    );
+}
+
+#[test]
+fn test_sort_file_contents_with_stacked_variants() {
+    let file_contents =
+        r#"<div class="md:hover:px-2 md:px-2 md:hover:flex md:flex md:focus:flex flex"></div>"#;
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        r#"<div class="flex md:flex md:px-2 md:hover:flex md:hover:px-2 md:focus:flex"></div>"#
+    );
+
+    let file_contents = r#"<div class="dark:md:hover:px-2 dark:md:hover:flex dark:hover:flex dark:md:flex dark:md:focus:!flex dark:md:custom"></div>"#;
+
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &default_options_for_test()),
+        r#"<div class="dark:md:flex dark:md:hover:flex dark:md:hover:px-2 dark:md:focus:!flex dark:hover:flex dark:md:custom"></div>"#
+    );
+}
+
+#[test]
+fn test_write_sorted_file_with_backup() {
+    let dir = TestDir::new("backup");
+
+    let options = Options {
+        backup: true,
+        ..default_options_for_test()
+    };
+    let unsorted = r#"<a class="px-2 flex"></a>"#;
+    let sorted = r#"<a class="flex px-2"></a>"#;
+
+    // an already sorted file is not written, so it has no backup
+    let unchanged = dir.join("unchanged.html");
+    fs::write(&unchanged, sorted).unwrap();
+    options
+        .write_sorted_file(&unchanged, sorted, sorted)
+        .unwrap();
+    let unchanged_backup = dir.join("unchanged.html.bak").exists();
+
+    // an older backup is replaced by the contents before this write
+    let changed = dir.join("changed.html");
+    fs::write(&changed, unsorted).unwrap();
+    fs::write(dir.join("changed.html.bak"), "older backup").unwrap();
+    options
+        .write_sorted_file(&changed, sorted, unsorted)
+        .unwrap();
+    let changed_contents = fs::read_to_string(&changed).unwrap();
+    let changed_backup = fs::read_to_string(dir.join("changed.html.bak")).unwrap();
+
+    // the backup can't be written over a directory, so the file is not changed
+    let failing = dir.join("failing.html");
+    fs::write(&failing, unsorted).unwrap();
+    fs::create_dir_all(dir.join("failing.html.bak")).unwrap();
+    let failed_write = options.write_sorted_file(&failing, sorted, unsorted);
+    let failing_contents = fs::read_to_string(&failing).unwrap();
+
+    assert!(!unchanged_backup);
+    assert_eq!(changed_contents, sorted);
+    assert_eq!(changed_backup, unsorted);
+    assert!(failed_write
+        .unwrap_err()
+        .to_string()
+        .starts_with("Unable to save the backup file"));
+    assert_eq!(failing_contents, unsorted);
+}
+
+#[test]
+fn test_options_apply_returns_the_outcome_without_writing() {
+    let dir = TestDir::new("apply");
+
+    fs::write(dir.join("changed.html"), r#"<a class="px-2 flex"></a>"#).unwrap();
+    fs::write(dir.join("unchanged.html"), r#"<a class="flex px-2"></a>"#).unwrap();
+    fs::write(dir.join("no-classes.html"), "<a></a>").unwrap();
+    fs::write(dir.join("ignored.html"), r#"<a class="px-2 flex"></a>"#).unwrap();
+
+    let options = Options {
+        ignored_files: build_glob_set(
+            &parse_ignored_files(&[dir.join("ignored.html").display().to_string()]).unwrap(),
+        )
+        .unwrap(),
+        ..default_options_for_test()
+    };
+
+    let outcomes: Vec<_> = [
+        "changed.html",
+        "unchanged.html",
+        "no-classes.html",
+        "ignored.html",
+    ]
+    .iter()
+    .map(|file_name| options.apply(&dir.join(file_name)).unwrap())
+    .collect();
+
+    let missing = options.apply(&dir.join("missing.html"));
+    let changed_contents = fs::read_to_string(dir.join("changed.html")).unwrap();
+
+    assert_eq!(
+        outcomes,
+        vec![
+            Outcome::Changed {
+                before: r#"<a class="px-2 flex"></a>"#.to_string(),
+                after: r#"<a class="flex px-2"></a>"#.to_string(),
+                changes: vec![ClassChange {
+                    before: "px-2 flex".to_string(),
+                    after: "flex px-2".to_string(),
+                    line: 1,
+                    column: 4,
+                }],
+            },
+            Outcome::Unchanged {
+                contents: r#"<a class="flex px-2"></a>"#.to_string()
+            },
+            Outcome::Skipped,
+            Outcome::Skipped,
+        ]
+    );
+    assert!(missing.is_err());
+    assert_eq!(changed_contents, r#"<a class="px-2 flex"></a>"#);
+}
+
+#[test]
+fn test_options_apply_keeps_the_byte_order_mark() {
+    let dir = TestDir::new("bom");
+
+    let bom = "\u{feff}";
+    fs::write(
+        dir.join("index.html"),
+        format!("{bom}<div class=\"underline px-4 py-2 flex items-center\"></div>\n<p>text</p>\n"),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("tokens.json"),
+        format!("{bom}{{ \"classes\": \"px-2 flex\" }}\n"),
+    )
+    .unwrap();
+
+    let options = Options {
+        max_line_length: Some(40),
+        json_field: Some("classes".to_string()),
+        ..default_options_for_test()
+    };
+
+    let html = options.apply(&dir.join("index.html")).unwrap();
+    let json = options.apply(&dir.join("tokens.json")).unwrap();
+
+    // the byte order mark is kept and is not a column, the next line lines up with `class`
+    match html {
+        Outcome::Changed { after, .. } => assert_eq!(
+            after,
+            format!("{bom}<div class=\"flex items-center py-2 px-4\n     underline\"></div>\n<p>text</p>\n")
+        ),
+        outcome => panic!("expected the file to change, got {outcome:?}"),
+    }
+
+    match json {
+        Outcome::Changed { after, .. } => {
+            assert_eq!(after, format!("{bom}{{ \"classes\": \"flex px-2\" }}\n"))
+        }
+        outcome => panic!("expected the file to change, got {outcome:?}"),
+    }
+}
+
+#[test]
+fn test_config_file_from_stdin_is_the_only_reader_of_stdin() {
+    let validate = |args: &[&str]| {
+        let cli = Cli::parse_from([&["rustywind"], args].concat());
+        validate_config_file_from_cli(&cli).map_err(|error| error.to_string())
+    };
+
+    assert_eq!(validate(&["--config-file", "-", "."]), Ok(()));
+    assert_eq!(
+        validate(&["--config-file", "rustywind.json", "--stdin"]),
+        Ok(())
+    );
+    assert_eq!(
+        validate(&["--config-file", "-", "--config-file", "-", "."]),
+        Err("--config-file - can only be passed once".to_string())
+    );
+    assert_eq!(
+        validate(&["--config-file", "-", "--stdin"]),
+        Err("--config-file - can't be used with --stdin, both would read stdin".to_string())
+    );
+    assert_eq!(
+        validate(&["--config-file", "-", "--files-from", "-"]),
+        Err("--config-file - can't be used with --files-from -, both would read stdin".to_string())
+    );
+}
+
+#[test]
+fn test_stdin_with_paths_or_files_modes_is_an_error() {
+    let validate = |args: &[&str]| {
+        let cli = Cli::parse_from([&["rustywind", "--stdin"], args].concat());
+        validate_stdin_from_cli(&cli).map_err(|error| error.to_string())
+    };
+
+    assert_eq!(validate(&[]), Ok(()));
+    assert_eq!(validate(&["--range", "0", "10"]), Ok(()));
+    assert_eq!(
+        validate(&["src", "index.html"]),
+        Err("--stdin can't be used with a path, got: src index.html".to_string())
+    );
+    assert_eq!(
+        validate(&["--write"]),
+        Err("--stdin can't be used with --write, there is no file to write to".to_string())
+    );
+    assert_eq!(
+        validate(&["--check-formatted"]),
+        Err("--stdin can't be used with --check-formatted".to_string())
+    );
+}
+
+#[test]
+fn test_dry_run_with_output_format_json_stays_a_dry_run() {
+    let write_mode = |args: &[&str]| {
+        let cli = Cli::try_parse_from([&["rustywind", "."], args].concat()).unwrap();
+        get_write_mode_from_cli(&cli)
+    };
+
+    assert!(matches!(
+        write_mode(&["--dry-run", "--output-format", "json"]),
+        WriteMode::DryRun
+    ));
+    assert!(matches!(
+        write_mode(&["--output-format", "json"]),
+        WriteMode::Json
+    ));
+    assert!(Cli::try_parse_from(["rustywind", ".", "--write", "--output-format", "json"]).is_err());
+}
+
+#[test]
+fn test_check_formatted_with_output_format_json_stays_a_check() {
+    let write_mode = |args: &[&str]| {
+        let cli = Cli::try_parse_from([&["rustywind", "."], args].concat()).unwrap();
+        get_write_mode_from_cli(&cli)
+    };
+
+    assert!(matches!(
+        write_mode(&["--check-formatted", "--output-format", "json"]),
+        WriteMode::CheckFormatted
+    ));
+    assert!(matches!(
+        write_mode(&["--check-formatted"]),
+        WriteMode::CheckFormatted
+    ));
+    assert!(Cli::try_parse_from(["rustywind", ".", "--diff", "--output-format", "json"]).is_err());
+}
+
+#[test]
+fn test_options_has_classes_without_sorting() {
+    let dir = TestDir::new("count");
+
+    fs::write(dir.join("classes.html"), r#"<a class="px-2 flex"></a>"#).unwrap();
+    fs::write(dir.join("no-classes.html"), "<a></a>").unwrap();
+    fs::write(dir.join("ignored.html"), r#"<a class="px-2 flex"></a>"#).unwrap();
+    fs::write(dir.join("Component.vue"), r#"<a :class="'px-2 flex'"></a>"#).unwrap();
+
+    let options = Options {
+        ignored_files: build_glob_set(
+            &parse_ignored_files(&[dir.join("ignored.html").display().to_string()]).unwrap(),
+        )
+        .unwrap(),
+        ..default_options_for_test()
+    };
+
+    let has_classes: Vec<bool> = [
+        "classes.html",
+        "no-classes.html",
+        "ignored.html",
+        "Component.vue",
+    ]
+    .iter()
+    .map(|file_name| options.has_classes(&dir.join(file_name)).unwrap())
+    .collect();
+
+    let missing = options.has_classes(&dir.join("missing.html"));
+    let contents = fs::read_to_string(dir.join("classes.html")).unwrap();
+
+    assert_eq!(has_classes, vec![true, false, false, true]);
+    assert!(missing.is_err());
+    assert_eq!(contents, r#"<a class="px-2 flex"></a>"#);
+}
+
+#[test]
+fn test_ignored_files_with_paths_and_globs() {
+    let dir = TestDir::new("ignored");
+    fs::create_dir_all(dir.join("src/nested")).unwrap();
+    let dir = dir.canonicalize().unwrap();
+
+    for file_name in [
+        "index.html",
+        "page[1].html",
+        "src/a.generated.html",
+        "src/nested/b.generated.html",
+        "src/c.html",
+    ] {
+        fs::write(dir.join(file_name), "").unwrap();
+    }
+
+    let options = Options {
+        ignored_files: build_glob_set(
+            &parse_ignored_files(&[
+                dir.join("page[1].html").display().to_string(),
+                format!("{}/src/**/*.generated.html", dir.display()),
+                dir.join("missing.html").display().to_string(),
+            ])
+            .unwrap(),
+        )
+        .unwrap(),
+        ..default_options_for_test()
+    };
+
+    let ignored: Vec<bool> = [
+        "index.html",
+        "page[1].html",
+        "src/a.generated.html",
+        "src/nested/b.generated.html",
+        "src/c.html",
+    ]
+    .iter()
+    .map(|file_name| options.is_ignored(&dir.join(file_name)))
+    .collect();
+
+    let invalid_glob = parse_ignored_files(&["src/{a".to_string()]);
+
+    assert_eq!(ignored, vec![false, true, true, true, false]);
+    assert!(invalid_glob.is_err());
+}
+
+#[test]
+fn test_init_writes_a_config_template_that_keeps_the_defaults() {
+    let dir = TestDir::new("init");
+
+    let path = init::write_config_template(&dir, false).unwrap();
+    let not_overwritten = init::write_config_template(&dir, false);
+    fs::write(&path, "{}").unwrap();
+    let overwritten = init::write_config_template(&dir, true);
+    let contents = fs::read_to_string(&path).unwrap();
+
+    assert!(not_overwritten.is_err());
+    assert!(overwritten.is_ok());
+    assert_eq!(contents, init::CONFIG_TEMPLATE);
+
+    let config = parse_config_file("rustywind.json", &contents).unwrap();
+    assert_eq!(
+        config.sort_order,
+        Some(SortOrder::Extend {
+            prepend: vec![],
+            append: vec![],
+        })
+    );
+    assert!(parse_custom_regex(&config.custom_regex.unwrap())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_sort_file_contents_with_lowercase_classes() {
+    let file_contents = r#"<div class="px-2 Flex flex MD:PX-4 bg-[URL(A.png)]"></div>"#;
+
+    let options = Options {
+        lowercase_classes: true,
+        ..default_options_for_test()
+    };
+
+    // `Flex` is a duplicate of `flex` once lowercased, the arbitrary value keeps its case
+    assert_eq!(
+        utils::sort_file_contents(file_contents, &options),
+        r#"<div class="flex px-2 md:px-4 bg-[URL(A.png)]"></div>"#
+    );
+}
+
+#[test]
+fn test_no_dedup_keeps_every_class() {
+    let allow_duplicates = |args: &[&str]| {
+        let cli = Cli::try_parse_from([&["rustywind", "."], args].concat()).unwrap();
+        get_allow_duplicates_from_cli(&cli)
+    };
+
+    assert!(!allow_duplicates(&[]));
+    assert!(allow_duplicates(&["--no-dedup"]));
+    assert!(allow_duplicates(&["--allow-duplicates"]));
+    assert!(Cli::try_parse_from(["rustywind", ".", "--no-dedup", "--warn-duplicates"]).is_err());
+    assert!(Cli::try_parse_from(["rustywind", ".", "--no-dedup", "--fail-on-duplicate"]).is_err());
+
+    let options = Options {
+        allow_duplicates: true,
+        lowercase_classes: true,
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::sort_file_contents(r#"<a class="px-2 Flex flex px-2"></a>"#, &options),
+        r#"<a class="flex flex px-2 px-2"></a>"#
+    );
+}
+
+#[test]
+fn test_find_unknown_classes() {
+    let file_contents = r#"
+<a class="flexx px-2 md:flexx hover:px-2 my-card !px-4 -mt-2"></a>
+<b class="flexx js-hook"></b>
+"#;
+
+    let options = Options {
+        ignored_classes: vec!["js-hook".to_string()],
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::find_unknown_classes(file_contents, &options.regex, &options),
+        vec!["flexx", "md:flexx", "my-card", "flexx"]
+    );
+
+    // the classes are looked up as they are sorted
+    let options = Options {
+        lowercase_classes: true,
+        prefix: Some("tw-".to_string()),
+        ..default_options_for_test()
+    };
+
+    assert_eq!(
+        utils::find_unknown_classes(
+            r#"<a class="TW-PX-2 px-2 md:tw-flex">"#,
+            &options.regex,
+            &options
+        ),
+        vec!["px-2"]
+    );
+}
@@ -1,65 +1,927 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
 
 use itertools::Itertools;
-use regex::Captures;
+use regex::{Captures, Regex};
+use serde::Serialize;
 
-use crate::consts::{VARIANTS, VARIANT_SEARCHER};
-use crate::defaults::{RE, SORTER};
-use crate::options::{FinderRegex, Options, Sorter};
+use crate::consts::{BREAKPOINTS, VARIANTS, VARIANT_SEARCHER};
+use crate::defaults::{CLASS_ATTRIBUTE_START_RE, DOTTED_CLASS_RE, MARKER_CLASSES, RE};
+use crate::options::{
+    ClassSeparator, CustomClassOrder, CustomClassPosition, FinderRegex, IgnoredClassPosition,
+    Options, RegexPair, Sorter,
+};
 
 pub fn has_classes(file_contents: &str, options: &Options) -> bool {
-    let regex = match &options.regex {
-        FinderRegex::DefaultRegex => &RE,
-        FinderRegex::CustomRegex(regex) => regex,
+    has_classes_with_regex(file_contents, &options.regex)
+}
+
+/// Same as `has_classes` but with a specific finder, see `Options::regex_for_path`
+pub fn has_classes_with_regex(file_contents: &str, regex: &FinderRegex) -> bool {
+    match regex {
+        // every match of the default finder contains `class`, looking for it first is a lot
+        // cheaper than running the regex on the files without any class attribute. The custom
+        // finders are not prescreened, the regex crate already looks for the literal prefix of a
+        // regex before running it, see `cargo bench`, and a regex without one has nothing to look for
+        FinderRegex::DefaultRegex => file_contents.contains("class") && RE.is_match(file_contents),
+        FinderRegex::CustomRegex(regex) => regex.is_match(file_contents),
+        FinderRegex::CustomRegexEntries(entries) => entries
+            .iter()
+            .any(|entry| entry.container_regex.is_match(file_contents)),
+    }
+}
+
+/// Finds the quoted `class` and `className` attributes that the finder didn't match, so their
+/// classes are not sorted, ex: a `class="...` without its closing quote. Returns the line of each one
+pub fn find_unmatched_class_attributes(file_contents: &str, regex: &FinderRegex) -> Vec<usize> {
+    let matched: Vec<Range<usize>> = match regex {
+        FinderRegex::DefaultRegex => RE
+            .find_iter(file_contents)
+            .map(|found| found.range())
+            .collect(),
+        FinderRegex::CustomRegex(regex) => regex
+            .find_iter(file_contents)
+            .map(|found| found.range())
+            .collect(),
+        FinderRegex::CustomRegexEntries(entries) => entries
+            .iter()
+            .flat_map(|entry| entry.container_regex.find_iter(file_contents))
+            .map(|found| found.range())
+            .collect(),
+    };
+
+    CLASS_ATTRIBUTE_START_RE
+        .captures_iter(file_contents)
+        .filter_map(|caps| caps.get(1))
+        .filter(|attribute| {
+            // an empty attribute has no classes to sort
+            let quote = &attribute.as_str()[attribute.as_str().len() - 1..];
+            !file_contents[attribute.end()..].starts_with(quote)
+        })
+        .filter(|attribute| {
+            !matched
+                .iter()
+                .any(|range| range.contains(&attribute.start()))
+        })
+        .map(|attribute| file_contents[..attribute.start()].matches('\n').count() + 1)
+        .collect()
+}
+
+/// The classes found by the finder that are not in the sorter, with or without their variants,
+/// ex: a typo like `flexx` or a class of another Tailwind version. The ignored classes are left out
+pub fn find_unknown_classes(
+    file_contents: &str,
+    regex: &FinderRegex,
+    options: &Options,
+) -> Vec<String> {
+    let class_strings = match regex {
+        FinderRegex::DefaultRegex => find_class_strings(file_contents, &RE),
+        FinderRegex::CustomRegex(regex) => find_class_strings(file_contents, regex),
+        FinderRegex::CustomRegexEntries(entries) => entries
+            .iter()
+            .flat_map(|entry| {
+                entry
+                    .container_regex
+                    .find_iter(file_contents)
+                    .flat_map(|container| {
+                        find_class_strings(container.as_str(), &entry.class_regex)
+                    })
+            })
+            .collect(),
     };
 
-    regex.is_match(file_contents)
+    class_strings
+        .into_iter()
+        .flat_map(|(class_string, is_dotted)| match is_dotted {
+            true => DOTTED_CLASS_RE
+                .captures_iter(class_string)
+                .filter_map(|caps| caps.get(1).or_else(|| caps.get(2)))
+                .map(|class| class.as_str())
+                .filter(|class| is_unknown_class(class, options))
+                .map(str::to_string)
+                .collect(),
+            false => find_unknown_classes_in(class_string, options),
+        })
+        .collect()
+}
+
+/// The class strings captured by the regex, and whether each one is a dotted class chain
+fn find_class_strings<'a>(file_contents: &'a str, regex: &Regex) -> Vec<(&'a str, bool)> {
+    regex
+        .captures_iter(file_contents)
+        .filter_map(|caps| {
+            let classes = caps.iter().skip(1).flatten().next()?;
+            let is_dotted =
+                caps.name("dotted").map(|dotted| dotted.range()) == Some(classes.range());
+            Some((classes.as_str(), is_dotted))
+        })
+        .collect()
+}
+
+/// Same as `find_unknown_classes` for a single class string, the classes are looked up as they
+/// are sorted, so with `lowercase_classes` they are lowercased and with `ClassSeparator::Comma`
+/// they are unquoted
+pub(crate) fn find_unknown_classes_in(class_string: &str, options: &Options) -> Vec<String> {
+    let class_string = match options.lowercase_classes {
+        true => Cow::Owned(lowercase_classes(class_string)),
+        false => Cow::Borrowed(class_string),
+    };
+
+    split_classes_by(&class_string, options.class_separator)
+        .map(unquote)
+        .filter(|class| is_unknown_class(class, options))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether the class would be sorted as a custom class, the ignored classes are never unknown
+fn is_unknown_class(class: &str, options: &Options) -> bool {
+    let sorter = get_sorter(options);
+    let prefix = options.prefix.as_deref();
+
+    if options
+        .ignored_classes
+        .iter()
+        .any(|ignored| ignored == class)
+        || get_class_placement(class, sorter, prefix).is_some()
+    {
+        return false;
+    }
+
+    let (variants, utility) = strip_variants(class);
+    variants.is_empty() || get_class_placement(utility, sorter, prefix).is_none()
+}
+
+/// A class string that was reordered while sorting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassChange {
+    pub before: String,
+    pub after: String,
+    /// The line of the start of the class attribute, starting at 1
+    pub line: usize,
+    /// The column of the start of the class attribute in characters, starting at 1
+    pub column: usize,
+}
+
+impl ClassChange {
+    /// The number of duplicated classes that were removed while sorting
+    pub fn removed_duplicates(&self) -> usize {
+        split_classes(&self.before).count() - split_classes(&self.after).count()
+    }
+
+    /// The classes that were written more than once in the original class string
+    pub fn duplicated_classes(&self) -> Vec<&str> {
+        split_classes(&self.before).duplicates().collect()
+    }
 }
 
 pub fn sort_file_contents<'a>(file_contents: &'a str, options: &Options) -> Cow<'a, str> {
-    let regex = match &options.regex {
-        FinderRegex::DefaultRegex => &RE,
-        FinderRegex::CustomRegex(regex) => regex,
+    sort_file_contents_with_changes(file_contents, options).0
+}
+
+/// Same as `sort_file_contents` but also returns every class string that was reordered
+pub fn sort_file_contents_with_changes<'a>(
+    file_contents: &'a str,
+    options: &Options,
+) -> (Cow<'a, str>, Vec<ClassChange>) {
+    sort_file_contents_with_regex(file_contents, &options.regex, options)
+}
+
+/// Same as `sort_file_contents_with_changes` but with a specific finder, see `Options::regex_for_path`
+pub fn sort_file_contents_with_regex<'a>(
+    file_contents: &'a str,
+    regex: &FinderRegex,
+    options: &Options,
+) -> (Cow<'a, str>, Vec<ClassChange>) {
+    let mut changes = vec![];
+    let line_ending = LineEnding::detect(file_contents);
+
+    let sorted_contents = match regex {
+        FinderRegex::DefaultRegex => sort_with_regex(
+            file_contents,
+            &RE,
+            options,
+            true,
+            "",
+            line_ending,
+            &mut changes,
+        ),
+        FinderRegex::CustomRegex(regex) => sort_with_regex(
+            file_contents,
+            regex,
+            options,
+            true,
+            "",
+            line_ending,
+            &mut changes,
+        ),
+        FinderRegex::CustomRegexEntries(entries) => {
+            let mut contents = Cow::Borrowed(file_contents);
+
+            for entry in entries {
+                if let Cow::Owned(sorted) =
+                    sort_with_regex_pair(&contents, entry, options, line_ending, &mut changes)
+                {
+                    contents = Cow::Owned(sorted);
+                }
+            }
+
+            contents
+        }
     };
 
+    // replace_all returns an owned string for any match, even if nothing was changed
+    if sorted_contents == file_contents {
+        return (Cow::Borrowed(file_contents), changes);
+    }
+
+    (sorted_contents, changes)
+}
+
+fn sort_with_regex_pair<'a>(
+    file_contents: &'a str,
+    entry: &RegexPair,
+    options: &Options,
+    line_ending: LineEnding,
+    changes: &mut Vec<ClassChange>,
+) -> Cow<'a, str> {
+    let mut positions = Positions::new(file_contents);
+
+    entry
+        .container_regex
+        .replace_all(file_contents, |caps: &Captures| {
+            if !is_in_range(caps, options) {
+                return caps[0].to_string();
+            }
+
+            let container_start = caps.get(0).map_or(0, |found| found.start());
+            let line_prefix = line_before(file_contents, container_start);
+            let container_changes = changes.len();
+
+            let sorted = sort_with_regex(
+                &caps[0],
+                &entry.class_regex,
+                options,
+                false,
+                line_prefix,
+                line_ending,
+                changes,
+            )
+            .into_owned();
+
+            // the positions of the changes are in the container, move them to the whole contents
+            if changes.len() > container_changes {
+                let (line, column) = positions.at(container_start);
+
+                for change in &mut changes[container_changes..] {
+                    if change.line == 1 {
+                        change.column += column - 1;
+                    }
+                    change.line += line - 1;
+                }
+            }
+
+            sorted
+        })
+}
+
+/// `check_range` is false when sorting inside of a container, the container was already checked.
+/// `line_prefix` is the text on the same line before `file_contents`, when it's a container.
+/// `line_ending` is the line ending of the whole file, used for the line breaks added by sorting
+fn sort_with_regex<'a>(
+    file_contents: &'a str,
+    regex: &Regex,
+    options: &Options,
+    check_range: bool,
+    line_prefix: &str,
+    line_ending: LineEnding,
+    changes: &mut Vec<ClassChange>,
+) -> Cow<'a, str> {
+    let mut positions = Positions::new(file_contents);
+
     regex.replace_all(file_contents, |caps: &Captures| {
-        let classes = &caps[1];
-        let sorted_classes = sort_classes(classes, options);
+        if check_range && !is_in_range(caps, options) {
+            return caps[0].to_string();
+        }
 
-        caps[0].replace(classes, &sorted_classes)
+        // the classes are in the first capture group that matched, so a regex can have
+        // alternatives, ex: `"([^"]*)"|'([^']*)'`
+        let (found, classes) = match (caps.get(0), caps.iter().skip(1).flatten().next()) {
+            (Some(found), Some(classes)) => (found, classes),
+            _ => return caps[0].to_string(),
+        };
+        let is_dotted = caps.name("dotted").map(|dotted| dotted.range()) == Some(classes.range());
+        let class_range = classes.start() - found.start()..classes.end() - found.start();
+        let classes = classes.as_str();
+
+        if is_dotted {
+            let sorted_classes = sort_dotted_classes(classes, options);
+
+            if classes == sorted_classes {
+                return caps[0].to_string();
+            }
+
+            let (line, column) = positions.at(found.start());
+            changes.push(ClassChange {
+                before: classes.to_string(),
+                after: sorted_classes.clone(),
+                line,
+                column,
+            });
+
+            let mut replaced = found.as_str().to_string();
+            replaced.replace_range(class_range, &sorted_classes);
+            return replaced;
+        }
+
+        let mut sorted_classes = sort_classes(classes, options);
+
+        if let Some(max_line_length) = options.max_line_length {
+            let before = line_before(file_contents, found.start());
+            // on the first line of a container, the line starts before the container
+            let attribute_line_prefix = if before.len() == found.start() {
+                Cow::Owned(format!("{line_prefix}{before}"))
+            } else {
+                Cow::Borrowed(before)
+            };
+
+            sorted_classes = wrap_classes(
+                &sorted_classes,
+                max_line_length,
+                &attribute_line_prefix,
+                &found.as_str()[..class_range.start],
+                options,
+            );
+        }
+
+        // the line breaks of `--max-line-length` and of the `groupSeparator` are always `\n`
+        if line_ending == LineEnding::CrLf && sorted_classes.contains('\n') {
+            sorted_classes = line_ending.apply(&sorted_classes);
+        }
+
+        if classes == sorted_classes {
+            return caps[0].to_string();
+        }
+
+        let (line, column) = positions.at(found.start());
+        changes.push(ClassChange {
+            before: classes.to_string(),
+            after: sorted_classes.clone(),
+            line,
+            column,
+        });
+
+        // only the captured classes are replaced, the same text could also be around them
+        let mut replaced = found.as_str().to_string();
+        replaced.replace_range(class_range, &sorted_classes);
+        replaced
     })
 }
 
-fn sort_classes(class_string: &str, options: &Options) -> String {
-    let sorter: &HashMap<String, usize> = match &options.sorter {
-        Sorter::DefaultSorter => &SORTER,
-        Sorter::CustomSorter(custom_sorter) => custom_sorter,
+/// The line ending of a file, detected from its first line break, so the line breaks added while
+/// sorting don't mix `\n` into a file with `\r\n` line endings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    fn detect(contents: &str) -> Self {
+        match contents.find('\n') {
+            Some(index) if contents[..index].ends_with('\r') => LineEnding::CrLf,
+            _ => LineEnding::Lf,
+        }
+    }
+
+    /// Replaces the `\n` line breaks with this line ending, the `\r\n` ones are kept as is
+    fn apply(self, text: &str) -> String {
+        match self {
+            LineEnding::Lf => text.to_string(),
+            LineEnding::CrLf => text
+                .split("\r\n")
+                .map(|part| part.replace('\n', "\r\n"))
+                .join("\r\n"),
+        }
+    }
+}
+
+/// The line and column of the byte offsets of a text, the offsets have to be increasing so the
+/// text is only read once
+pub(crate) struct Positions<'a> {
+    contents: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Positions<'a> {
+    pub(crate) fn new(contents: &'a str) -> Self {
+        Positions {
+            contents,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub(crate) fn at(&mut self, offset: usize) -> (usize, usize) {
+        for char in self.contents[self.offset..offset].chars() {
+            match char {
+                '\n' => {
+                    self.line += 1;
+                    self.column = 1;
+                }
+                // the byte order mark is not a column
+                '\u{feff}' => (),
+                _ => self.column += 1,
+            }
+        }
+
+        self.offset = offset;
+        (self.line, self.column)
+    }
+}
+
+/// The text of the line that is before the offset, without the byte order mark of the first line
+fn line_before(contents: &str, offset: usize) -> &str {
+    let before = &contents[..offset];
+
+    match before.rfind('\n') {
+        Some(line_start) => &before[line_start + 1..],
+        None => strip_bom(before),
+    }
+}
+
+/// Removes the UTF-8 byte order mark that some editors write at the start of the files, it is
+/// read as part of the contents and written back as is, but it's not a column or valid JSON
+pub fn strip_bom(contents: &str) -> &str {
+    contents.strip_prefix('\u{feff}').unwrap_or(contents)
+}
+
+/// Wraps the sorted classes onto multiple lines when they go past `max_line_length` columns, the
+/// next lines are indented to the start of the attribute, ex: `class="..."`. `line_prefix` is the
+/// text of the line before the attribute and `attribute_start` the attribute up to its classes.
+/// A tab is `options.tab_width` columns wide
+fn wrap_classes(
+    classes: &str,
+    max_line_length: usize,
+    line_prefix: &str,
+    attribute_start: &str,
+    options: &Options,
+) -> String {
+    let indent_width = columns(line_prefix, 0, options.tab_width);
+
+    let indent: String = if options.use_tabs {
+        let tabs = "\t".repeat(indent_width / options.tab_width);
+        tabs + &" ".repeat(indent_width % options.tab_width)
+    } else {
+        // the tabs of the line are kept, so the next lines use the same indentation as the line
+        line_prefix
+            .chars()
+            .map(|char| if char == '\t' { '\t' } else { ' ' })
+            .collect()
     };
 
+    let mut wrapped = String::with_capacity(classes.len());
+    let mut column = columns(attribute_start, indent_width, options.tab_width);
+
+    for (index, class) in split_classes(classes).enumerate() {
+        let width = class.chars().count();
+
+        if index > 0 {
+            if column + 1 + width > max_line_length {
+                wrapped.push('\n');
+                wrapped.push_str(&indent);
+                column = indent_width;
+            } else {
+                wrapped.push(' ');
+                column += 1;
+            }
+        }
+
+        wrapped.push_str(class);
+        column += width;
+    }
+
+    wrapped
+}
+
+/// The column after `text` when it starts at `column`, a tab moves to the next multiple of
+/// `tab_width`
+fn columns(text: &str, column: usize, tab_width: usize) -> usize {
+    text.chars().fold(column, |column, char| match char {
+        '\t' => column + tab_width - column % tab_width,
+        _ => column + 1,
+    })
+}
+
+/// Whether the whole match is inside of `options.range`, always true when no range is set
+fn is_in_range(caps: &Captures, options: &Options) -> bool {
+    match (&options.range, caps.get(0)) {
+        (Some(range), Some(found)) => range.start <= found.start() && found.end() <= range.end,
+        _ => true,
+    }
+}
+
+fn get_sorter(options: &Options) -> &HashMap<String, usize> {
+    match &options.sorter {
+        Sorter::DefaultSorter => options.tailwind_version.default_sorter(),
+        Sorter::CustomSorter(custom_sorter) => custom_sorter,
+    }
+}
+
+/// Sorts a chain of classes that each start with a `.`, ex: `.px-2.flex` in Maud. A class can be
+/// quoted, ex: `."hover:flex"`, and can be followed by a toggle, ex: `.flex[active]`, that moves
+/// with it. The duplicates are kept, removing them could change what the toggles do
+fn sort_dotted_classes(class_chain: &str, options: &Options) -> String {
+    let classes: Vec<(&str, &str)> = DOTTED_CLASS_RE
+        .captures_iter(class_chain)
+        .filter_map(|caps| {
+            let class = caps.get(1).or_else(|| caps.get(2))?.as_str();
+            Some((class, caps.get(0)?.as_str()))
+        })
+        .collect();
+
+    let sorted_classes = sort_classes_vec(
+        classes.iter().map(|(class, _part)| *class),
+        get_sorter(options),
+        options,
+    );
+
+    sorted_classes
+        .iter()
+        .filter_map(|sorted_class| {
+            classes
+                .iter()
+                .find(|(class, _part)| std::ptr::eq(*class, *sorted_class))
+                .map(|(_class, part)| *part)
+        })
+        .collect()
+}
+
+pub(crate) fn sort_classes(class_string: &str, options: &Options) -> String {
+    match options.lowercase_classes {
+        true => sort_class_string(&lowercase_classes(class_string), options),
+        false => sort_class_string(class_string, options),
+    }
+}
+
+/// Lowercases the classes outside of the brackets and parentheses, the arbitrary values can be
+/// case-sensitive, ex: `bg-[url('A.png')]` or `bg-(--My-Color)`
+fn lowercase_classes(class_string: &str) -> String {
+    let mut lowercased = String::with_capacity(class_string.len());
+    let mut depth = 0usize;
+
+    for char in class_string.chars() {
+        match char {
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+
+        match depth {
+            0 => lowercased.extend(char.to_lowercase()),
+            _ => lowercased.push(char),
+        }
+    }
+
+    lowercased
+}
+
+fn sort_class_string(class_string: &str, options: &Options) -> String {
+    let sorter = get_sorter(options);
+
+    if options.class_separator == ClassSeparator::Comma {
+        let classes: Vec<&str> = split_classes_by(class_string, ClassSeparator::Comma).collect();
+
+        if has_comma_separator(class_string, &classes) {
+            return sort_comma_separated_classes(class_string, &classes, sorter, options);
+        }
+    }
+
     let str_vec = if options.allow_duplicates {
-        sort_classes_vec(class_string.split_ascii_whitespace(), sorter)
+        sort_classes_vec(split_classes(class_string), sorter, options)
     } else {
-        sort_classes_vec(class_string.split_ascii_whitespace().unique(), sorter)
+        sort_classes_vec(split_classes(class_string).unique(), sorter, options)
+    };
+
+    if options.preserve_whitespace {
+        let original_classes: Vec<&str> = split_classes(class_string).collect();
+        return join_with_original_separators(class_string, &original_classes, str_vec);
+    }
+
+    // a comma would be injected into a whitespace separated list, ex: a `class` attribute
+    let join_separator = match options.join_separator.contains(',') {
+        true => " ",
+        false => options.join_separator.as_str(),
     };
 
     let mut string = String::with_capacity(str_vec.len() * 2);
+    let mut group = None;
+
+    for (index, class) in str_vec.into_iter().enumerate() {
+        let class_group = options
+            .group_prefixes
+            .iter()
+            .position(|prefix| class.starts_with(prefix.as_str()));
 
-    for str in str_vec {
-        string.push_str(str);
-        string.push(' ')
+        if index > 0 {
+            match class_group {
+                Some(_) if class_group != group => string.push_str(&options.group_separator),
+                _ => string.push_str(join_separator),
+            }
+        }
+
+        if class_group.is_some() {
+            group = class_group;
+        }
+
+        string.push_str(class);
     }
 
-    string.pop();
     string
 }
 
+/// Whether one of the separators between the classes has a comma, a comma inside of brackets,
+/// ex: `grid-cols-[1fr,2fr]`, is part of its class
+fn has_comma_separator(class_string: &str, classes: &[&str]) -> bool {
+    let offset_of = |class: &str| class.as_ptr() as usize - class_string.as_ptr() as usize;
+
+    classes.iter().tuple_windows().any(|(previous, next)| {
+        class_string[offset_of(previous) + previous.len()..offset_of(next)].contains(',')
+    })
+}
+
+/// Sorts a comma separated list of classes, ex: `"px-2", "flex"`. The quotes around a class are
+/// ignored when sorting and the classes are joined with the comma `join_separator`, or with the
+/// original separators when it's whitespace, so the commas are never dropped
+fn sort_comma_separated_classes(
+    class_string: &str,
+    classes: &[&str],
+    sorter: &HashMap<String, usize>,
+    options: &Options,
+) -> String {
+    // a quoted string with more than one class, ex: `"px-2 flex", "mt-2"`, is left as is
+    let is_partly_quoted = |class: &&str| {
+        unquote(class) == *class && (class.starts_with(['"', '\'']) || class.ends_with(['"', '\'']))
+    };
+
+    if classes.iter().any(is_partly_quoted) {
+        return class_string.to_string();
+    }
+
+    let unquoted: Vec<(&str, &str)> = classes
+        .iter()
+        .map(|class| (unquote(class), *class))
+        .collect();
+    let unquoted_classes = unquoted.iter().map(|(unquoted, _class)| *unquoted);
+
+    let sorted_classes = if options.allow_duplicates {
+        sort_classes_vec(unquoted_classes, sorter, options)
+    } else {
+        sort_classes_vec(unquoted_classes.unique(), sorter, options)
+    };
+
+    let sorted_classes = sorted_classes
+        .iter()
+        .filter_map(|sorted_class| {
+            unquoted
+                .iter()
+                .find(|(unquoted, _class)| std::ptr::eq(*unquoted, *sorted_class))
+                .map(|(_unquoted, class)| *class)
+        })
+        .collect();
+
+    match options.join_separator.contains(',') {
+        true => join_with_separator(
+            class_string,
+            classes,
+            sorted_classes,
+            &options.join_separator,
+        ),
+        false => join_with_original_separators(class_string, classes, sorted_classes),
+    }
+}
+
+/// Joins the sorted classes with the separator, the text before the first and after the last
+/// original class is kept
+fn join_with_separator(
+    class_string: &str,
+    original_classes: &[&str],
+    classes: Vec<&str>,
+    separator: &str,
+) -> String {
+    let offset_of = |class: &str| class.as_ptr() as usize - class_string.as_ptr() as usize;
+
+    let (first, last) = match (original_classes.first(), original_classes.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return class_string.to_string(),
+    };
+
+    format!(
+        "{}{}{}",
+        &class_string[..offset_of(first)],
+        classes.join(separator),
+        &class_string[offset_of(last) + last.len()..]
+    )
+}
+
+/// Removes the quotes around a class, ex: `"px-2"`
+fn unquote(class: &str) -> &str {
+    match (class.chars().next(), class.chars().last()) {
+        (Some(first @ ('"' | '\'')), Some(last)) if class.len() > 1 && first == last => {
+            &class[1..class.len() - 1]
+        }
+        _ => class,
+    }
+}
+
+/// Joins the sorted classes with the separators found around and between the original classes,
+/// so a multi-line class list keeps its line breaks and indentation. If duplicates were removed
+/// the extra separators are dropped, the ones without a line break first
+fn join_with_original_separators(
+    class_string: &str,
+    original_classes: &[&str],
+    classes: Vec<&str>,
+) -> String {
+    let offset_of = |class: &str| class.as_ptr() as usize - class_string.as_ptr() as usize;
+
+    let (first, last) = match (original_classes.first(), original_classes.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return class_string.to_string(),
+    };
+
+    let leading = &class_string[..offset_of(first)];
+    let trailing = &class_string[offset_of(last) + last.len()..];
+
+    let mut separators: Vec<&str> = original_classes
+        .iter()
+        .tuple_windows()
+        .map(|(previous, next)| {
+            &class_string[offset_of(previous) + previous.len()..offset_of(next)]
+        })
+        .collect();
+
+    while separators.len() >= classes.len().max(1) {
+        let index = separators
+            .iter()
+            .rposition(|separator| !separator.contains('\n'))
+            .unwrap_or(separators.len() - 1);
+
+        separators.remove(index);
+    }
+
+    let mut string = String::with_capacity(class_string.len());
+    string.push_str(leading);
+
+    for (index, class) in classes.iter().enumerate() {
+        if index > 0 {
+            string.push_str(separators[index - 1]);
+        }
+
+        string.push_str(class);
+    }
+
+    string.push_str(trailing);
+    string
+}
+
+/// Splits a class string into its classes, any kind of whitespace (including unicode whitespace
+/// like non-breaking spaces) separates classes so that the same class is always the same token.
+/// Whitespace inside of brackets is part of the class, ex: `bg-[url('a b.png')]`
+pub fn split_classes(class_string: &str) -> impl Iterator<Item = &str> {
+    split_classes_by(class_string, ClassSeparator::Whitespace)
+}
+
+/// Same as `split_classes`, with `ClassSeparator::Comma` the commas outside of brackets also
+/// separate the classes
+fn split_classes_by(class_string: &str, separator: ClassSeparator) -> impl Iterator<Item = &str> {
+    let is_separator = move |char: char| match separator {
+        ClassSeparator::Whitespace => char.is_whitespace(),
+        ClassSeparator::Comma => char.is_whitespace() || char == ',',
+    };
+
+    let mut rest = class_string;
+
+    std::iter::from_fn(move || {
+        rest = rest.trim_start_matches(is_separator);
+
+        if rest.is_empty() {
+            return None;
+        }
+
+        let mut depth = 0usize;
+        let end = rest
+            .char_indices()
+            .find(|&(_index, char)| {
+                match char {
+                    '[' => depth += 1,
+                    ']' => depth = depth.saturating_sub(1),
+                    char if is_separator(char) && depth == 0 => return true,
+                    _ => (),
+                }
+
+                false
+            })
+            .map_or(rest.len(), |(index, _char)| index);
+
+        let (class, remaining) = rest.split_at(end);
+        rest = remaining;
+
+        Some(class)
+    })
+}
+
+/// With `pin_marker_classes`, the marker classes are placed first in their original order
 fn sort_classes_vec<'a>(
     classes: impl Iterator<Item = &'a str>,
     sorter: &HashMap<String, usize>,
+    options: &Options,
 ) -> Vec<&'a str> {
-    let enumerated_classes = classes.map(|class| ((class), sorter.get(class)));
+    let classes: Vec<&str> = classes.collect();
 
-    let mut tailwind_classes: Vec<(&str, &usize)> = vec![];
+    if options.pin_marker_classes && classes.iter().any(|class| is_marker_class(class, options)) {
+        let (marker_classes, other_classes): (Vec<&str>, Vec<&str>) = classes
+            .into_iter()
+            .partition(|class| is_marker_class(class, options));
+
+        let sorted_classes = sort_not_marker_classes(other_classes, sorter, options);
+
+        return [&marker_classes[..], &sorted_classes[..]].concat();
+    }
+
+    sort_not_marker_classes(classes, sorter, options)
+}
+
+/// Sorts the classes with the ignored classes placed according to the options
+fn sort_not_marker_classes<'a>(
+    classes: Vec<&'a str>,
+    sorter: &HashMap<String, usize>,
+    options: &Options,
+) -> Vec<&'a str> {
+    let is_ignored = |class: &&str| {
+        options
+            .ignored_classes
+            .iter()
+            .any(|ignored| ignored == class)
+    };
+
+    if !classes.iter().any(is_ignored) {
+        return sort_not_ignored_classes(classes, sorter, options);
+    }
+
+    let (ignored_classes, other_classes): (Vec<&str>, Vec<&str>) =
+        classes.iter().copied().partition(is_ignored);
+    let sorted_classes = sort_not_ignored_classes(other_classes, sorter, options);
+
+    match options.ignored_class_position {
+        IgnoredClassPosition::First => [&ignored_classes[..], &sorted_classes[..]].concat(),
+        IgnoredClassPosition::Last => [&sorted_classes[..], &ignored_classes[..]].concat(),
+        IgnoredClassPosition::Original => {
+            keep_custom_class_positions(&classes, &ignored_classes, sorted_classes.into_iter())
+        }
+    }
+}
+
+/// `container`, `group` and `peer`, with the tailwind `prefix` when it's set, and the named groups
+/// and peers, ex: `group/item`. The ignored classes are never marker classes
+fn is_marker_class(class: &str, options: &Options) -> bool {
+    if options
+        .ignored_classes
+        .iter()
+        .any(|ignored| ignored == class)
+    {
+        return false;
+    }
+
+    let class = match strip_tailwind_prefix(class, options.prefix.as_deref()) {
+        Some(class) => class,
+        None => return false,
+    };
+    let name = class
+        .split_once('/')
+        .map_or(class.as_ref(), |(name, _)| name);
+
+    MARKER_CLASSES.contains(&name) && (name != "container" || name == class.as_ref())
+}
+
+/// Sorts the tailwind classes, then the variant classes, the custom classes are placed according
+/// to the options
+fn sort_not_ignored_classes<'a>(
+    classes: Vec<&'a str>,
+    sorter: &HashMap<String, usize>,
+    options: &Options,
+) -> Vec<&'a str> {
+    let enumerated_classes = classes.iter().map(|&class| {
+        let class_placement = get_class_placement(class, sorter, options.prefix.as_deref());
+        (class, class_placement)
+    });
+
+    let mut tailwind_classes: Vec<(&str, (usize, bool))> = vec![];
     let mut custom_classes: Vec<&str> = vec![];
     let mut variants: HashMap<&str, Vec<&str>> = HashMap::new();
 
@@ -69,7 +931,7 @@ fn sort_classes_vec<'a>(
             None => match VARIANT_SEARCHER.find(class) {
                 Some(prefix_match) => {
                     let prefix = VARIANTS[prefix_match.pattern()];
-                    variants.entry(prefix).or_insert_with(Vec::new).push(class)
+                    variants.entry(prefix).or_default().push(class)
                 }
 
                 None => custom_classes.push(class),
@@ -86,42 +948,128 @@ fn sort_classes_vec<'a>(
 
     let mut sorted_variant_classes = vec![];
 
-    for key in VARIANTS.iter() {
+    let variant_order: Vec<&str> = get_variant_order(options).collect();
+    let variant_ranks: HashMap<&str, usize> = variant_order
+        .iter()
+        .enumerate()
+        .map(|(rank, variant)| (*variant, rank))
+        .collect();
+
+    for key in variant_order {
         let (mut sorted_classes, new_custom_classes) = sort_variant_classes(
             variants.remove(key).unwrap_or_default(),
             custom_classes,
-            key.len() + 1,
+            &variant_ranks,
             sorter,
+            options.prefix.as_deref(),
         );
 
         sorted_variant_classes.append(&mut sorted_classes);
         custom_classes = new_custom_classes
     }
 
-    [
-        &sorted_tailwind_classes[..],
-        &sorted_variant_classes[..],
-        &custom_classes[..],
-    ]
-    .concat()
+    if options.preserve_custom_position {
+        let sorted_classes = sorted_tailwind_classes
+            .into_iter()
+            .chain(sorted_variant_classes);
+
+        return keep_custom_class_positions(&classes, &custom_classes, sorted_classes);
+    }
+
+    if let CustomClassOrder::Alphabetical = options.custom_class_order {
+        custom_classes.sort();
+    }
+
+    match options.custom_class_position {
+        CustomClassPosition::First => [
+            &custom_classes[..],
+            &sorted_tailwind_classes[..],
+            &sorted_variant_classes[..],
+        ]
+        .concat(),
+        CustomClassPosition::Last => [
+            &sorted_tailwind_classes[..],
+            &sorted_variant_classes[..],
+            &custom_classes[..],
+        ]
+        .concat(),
+    }
+}
+
+/// Puts the custom classes back at their original position and fills the other positions with the
+/// sorted classes, ex: `px-2 my-card flex` becomes `flex my-card px-2`
+fn keep_custom_class_positions<'a>(
+    classes: &[&'a str],
+    custom_classes: &[&'a str],
+    mut sorted_classes: impl Iterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let custom_classes: HashSet<&str> = custom_classes.iter().copied().collect();
+
+    classes
+        .iter()
+        .filter_map(|&class| {
+            if custom_classes.contains(class) {
+                Some(class)
+            } else {
+                sorted_classes.next()
+            }
+        })
+        .collect()
 }
 
+/// The configured `variant_order` followed by the default order, the breakpoints are kept together
+/// in mobile-first order at the position of the first breakpoint
+fn get_variant_order(options: &Options) -> impl Iterator<Item = &str> {
+    let mut breakpoints_added = false;
+
+    options
+        .variant_order
+        .iter()
+        .map(String::as_str)
+        .chain(VARIANTS.iter().copied())
+        .flat_map(move |variant| {
+            if !BREAKPOINTS.contains(&variant) {
+                return vec![variant];
+            }
+
+            if std::mem::replace(&mut breakpoints_added, true) {
+                vec![]
+            } else {
+                BREAKPOINTS.to_vec()
+            }
+        })
+        .unique()
+}
+
+/// Sorts the classes that start with the same variant, stacked variants (`md:hover:px-2`) are
+/// sorted after the classes with a single variant, by the order of their other variants and then
+/// by the position of the utility
 fn sort_variant_classes<'a>(
     classes: Vec<&'a str>,
     mut custom_classes: Vec<&'a str>,
-    class_after: usize,
+    variant_ranks: &HashMap<&str, usize>,
     sorter: &HashMap<String, usize>,
+    prefix: Option<&str>,
 ) -> (Vec<&'a str>, Vec<&'a str>) {
     let mut tailwind_classes = Vec::with_capacity(classes.len());
 
     for class in classes {
-        match class.get(class_after..).and_then(|class| sorter.get(class)) {
-            Some(class_placement) => tailwind_classes.push((class, class_placement)),
-            None => custom_classes.push(class),
+        let (variants, utility) = strip_variants(class);
+
+        match get_class_placement(utility, sorter, prefix) {
+            Some(class_placement) if !variants.is_empty() => {
+                let stacked_variant_ranks: Vec<usize> = variants[1..]
+                    .iter()
+                    .map(|variant| variant_ranks.get(variant).copied().unwrap_or(usize::MAX))
+                    .collect();
+
+                tailwind_classes.push((class, (stacked_variant_ranks, class_placement)))
+            }
+            _ => custom_classes.push(class),
         }
     }
 
-    tailwind_classes.sort_by_key(|&(_class, class_placement)| class_placement);
+    tailwind_classes.sort_by(|(_, a_placement), (_, b_placement)| a_placement.cmp(b_placement));
 
     let sorted_classes = tailwind_classes
         .iter()
@@ -131,6 +1079,64 @@ fn sort_variant_classes<'a>(
     (sorted_classes, custom_classes)
 }
 
+/// Strips the chain of known variants from the class, ex: `dark:md:hover:px-2` returns
+/// `(["dark", "md", "hover"], "px-2")`
+fn strip_variants(class: &str) -> (Vec<&'static str>, &str) {
+    let mut variants = vec![];
+    let mut utility = class;
+
+    while let Some(prefix_match) = VARIANT_SEARCHER.find(utility) {
+        match utility[prefix_match.end()..].strip_prefix(':') {
+            Some(rest) => {
+                variants.push(VARIANTS[prefix_match.pattern()]);
+                utility = rest;
+            }
+            None => break,
+        }
+    }
+
+    (variants, utility)
+}
+
+/// Looks up the position of the class in the sorter, the important modifier (`!px-2`) is ignored
+/// so important classes are sorted with their regular counterparts. A negative utility (`-mt-2`)
+/// that is not in the sorter is placed right after its positive counterpart (`mt-2`). With a
+/// tailwind `prefix`, only the prefixed classes are tailwind classes
+fn get_class_placement(
+    class: &str,
+    sorter: &HashMap<String, usize>,
+    prefix: Option<&str>,
+) -> Option<(usize, bool)> {
+    let class = class.strip_prefix('!').unwrap_or(class);
+    let class = strip_tailwind_prefix(class, prefix)?;
+    let class = class.as_ref();
+
+    match (sorter.get(class), class.strip_prefix('-')) {
+        (Some(index), _) => Some((*index, false)),
+        (None, Some(positive_class)) => sorter.get(positive_class).map(|index| (*index, true)),
+        (None, None) => None,
+    }
+}
+
+/// Removes the tailwind `prefix` of the class, ex: `tw-px-2` is `px-2`, the negative sign goes
+/// before the prefix so `-tw-mt-2` is `-mt-2`. `None` when the class doesn't have the prefix
+fn strip_tailwind_prefix<'a>(class: &'a str, prefix: Option<&str>) -> Option<Cow<'a, str>> {
+    let prefix = match prefix {
+        Some(prefix) if !prefix.is_empty() => prefix,
+        _ => return Some(Cow::Borrowed(class)),
+    };
+
+    match class.strip_prefix(prefix) {
+        Some(utility) => Some(Cow::Borrowed(utility)),
+        None => class
+            .strip_prefix('-')?
+            .strip_prefix(prefix)
+            .map(|utility| Cow::Owned(format!("-{utility}"))),
+    }
+}
+
+#[cfg(test)]
+use crate::defaults::SORTER;
 #[cfg(test)]
 use pretty_assertions::assert_eq;
 
@@ -148,7 +1154,8 @@ fn test_sort_classes_vec() {
                 "flex"
             ]
             .into_iter(),
-            &*SORTER
+            &SORTER,
+            &Options::default()
         ),
         vec![
             "inline-block",